@@ -1,31 +1,81 @@
 use crate::{
+	common_ports::{MOST_COMMON_PORTS, TLS_PORTS},
+	config::{Config, Source},
+	domain::Domain,
 	modules,
-	modules::{HttpModule, Subdomain},
+	modules::{HttpModule, Impact, Kind, Metadata, Module, Protocol, ResponseCache, ScanContext, Subdomain, SubdomainModule},
+	scope,
+	scope::Scope,
+	selection::Selection,
+	targets::Target,
 	Error,
 	dns,
 	ports,
 };
-use reqwest::Client;
-use std::time::{Duration, Instant};
+use reqwest::{redirect, Client};
+use serde::Serialize;
+use url::Url;
+use std::{
+	net::IpAddr,
+	sync::Arc,
+	time::{Duration, Instant},
+};
 use futures::{stream, StreamExt};
 use std::collections::HashSet;
 
-pub fn modules() {
-	let http_modules = modules::get_http_modules();
-	let subdomain_modules = modules::get_subdomain_modules();
+// What `modules --json` prints for each module
+#[derive(Serialize)]
+struct ModuleInfo {
+	name: String,
+	kind: Kind,
+	description: String,
+	#[serde(flatten)]
+	metadata: Metadata,
+}
+
+pub fn modules(selection: &Selection, json: bool) -> Result<(), Error> {
+	let modules: Vec<ModuleInfo> = modules::get_all_modules()
+		.into_iter()
+		.map(|(kind, module)| ModuleInfo { name: module.name(), kind, description: module.description(), metadata: module.metadata() })
+		.filter(|module| selection.selects(&module.name, &module.metadata.tags))
+		.collect();
+
+	if json {
+		println!("{}", serde_json::to_string_pretty(&modules).map_err(|err| Error::Config(err.to_string()))?);
+		return Ok(());
+	}
 
-	println!("Subdomain modules");
-	for module in subdomain_modules {
-		println!("\t- {}: {}", module.name(), module.description());
+	for (kind, title) in [(Kind::Subdomain, "Subdomain modules"), (Kind::Http, "HTTP modules")] {
+		println!("{}", title);
+		for module in modules.iter().filter(|module| module.kind == kind) {
+			let metadata = &module.metadata;
+			println!("\t- {} ({}, {}): {}", module.name, metadata.severity, metadata.impact, module.description);
+			if !metadata.tags.is_empty() {
+				println!("\t  tags: {}", metadata.tags.join(", "));
+			}
+			if !metadata.references.is_empty() {
+				let references: Vec<String> = metadata.references.iter().map(ToString::to_string).collect();
+				println!("\t  references: {}", references.join(", "));
+			}
+			if !metadata.enabled_by_default {
+				println!("\t  disabled unless enabled in the configuration");
+			}
+		}
 	}
+	Ok(())
+}
 
-	println!("HTTP modules");
-	for module in http_modules {
-		println!("\t- {}: {}", module.name(), module.description());
+pub fn config_show(config: &Config, sources: &[Source]) -> Result<(), Error> {
+	println!("# Effective configuration, from:");
+	for source in sources {
+		println!("#\t- {}", source);
 	}
+	println!();
+	print!("{}", toml::to_string_pretty(config).map_err(|err| Error::Config(err.to_string()))?);
+	Ok(())
 }
 
-pub fn scan(target: &str, ports: u16, enumerate: bool, vuln: bool) -> Result<(), Error> {
+pub fn scan(targets: &[Target], config: &Config, scope: &Scope, enumerate: bool, vuln: bool, passive: bool, json: bool) -> Result<(), Error> {
 	
 	// Set up tokio async runtime
 	let rt = tokio::runtime::Builder::new_multi_thread()
@@ -34,32 +84,95 @@ pub fn scan(target: &str, ports: u16, enumerate: bool, vuln: bool) -> Result<(),
 		.expect("Building tokio's runtime");
 
 	// Set variables
-	let dns_resolver = dns::new_resolver();
-	let subdomain_modules = modules::get_subdomain_modules();
-	let http_client = Client::builder().timeout(Duration::from_secs(8)).build()?;
+	let scan_config = &config.scan;
+	let dns_resolver = dns::new_resolver(Duration::from_secs(scan_config.dns_timeout_secs));
+	let subdomain_modules = configured(modules::get_subdomain_modules(), config)?;
+	// Passive scans only query third parties and DNS resolvers, never the targets themselves
+	if passive {
+		if vuln {
+			return Err(Error::Config("HTTP modules send requests to the targets, --vuln cannot be used with --passive".to_string()));
+		}
+		for module in &subdomain_modules {
+			let impact = module.metadata().impact;
+			if impact != Impact::Passive {
+				return Err(Error::Config(format!("{} is {}, only passive modules run with --passive", module.name(), impact)));
+			}
+		}
+	}
+	// Make sure the HTTP modules are configured properly before starting, every job shares them
+	let http_modules: Vec<Arc<dyn HttpModule>> = configured(modules::get_http_modules(), config)?
+		.into_iter()
+		.map(Arc::from)
+		.collect();
+	let http_client = Client::builder()
+		.redirect(scoped_redirects(scope.clone()))
+		.build()?;
+	// Only the most common ports that are in scope are probed
+	let ports: Vec<u16> = MOST_COMMON_PORTS
+		.iter()
+		.copied()
+		.take(scan_config.ports as usize)
+		.filter(|port| match scope.check_port(*port) {
+			Ok(()) => true,
+			Err(reason) => {
+				scope::dropped("port scan", &format!("port {}", port), &reason);
+				false
+			}
+		})
+		.collect();
+	let port_timeout = Duration::from_millis(scan_config.port_timeout_ms);
 	// Set concurrency limits
-	let subdomain_concurrency = 20;
-	let dns_concurrency = 100;
-	let port_concurrency = 200;
-	let vuln_concurrency = 20;
+	let subdomain_concurrency = scan_config.subdomain_concurrency;
+	let dns_concurrency = scan_config.dns_concurrency;
+	let port_concurrency = scan_config.port_concurrency;
+	let host_concurrency = scan_config.host_concurrency;
+	let vuln_concurrency = scan_config.vuln_concurrency;
 
 	let scan_start = Instant::now();
 
 
 	rt.block_on(async move {
 
-		let subdomains: Vec<Subdomain>;
-		
+		let targets: Vec<&Target> = targets
+			.iter()
+			.filter(|target| in_scope(scope, "targets", &target.host()))
+			.collect();
+		let mut hosts: Vec<String> = targets.iter().map(|target| target.host()).collect();
+
 		if enumerate {
-			// Get subdomains
-			log::info!("Started subdomain enumeration for {}", target);
-			
-			let mut subs: Vec<String> = stream::iter(subdomain_modules.into_iter())
-				.map(|module| async move {
-					match module.enumerate(target).await {
-						Ok(new_subdomains) => Some(new_subdomains),
-						Err(err) => { 
-							log::error!("{}: {}", module.name(), err);
+			// Get the subdomains of every domain, IP addresses have none
+			let domains: Vec<&Domain> = targets
+				.iter()
+				.copied()
+				.filter_map(|target| match target {
+					Target::Domain(domain) => Some(domain),
+					Target::Ip(_) => None,
+				})
+				.collect();
+			log::info!("Started subdomain enumeration for {} domain(s)", domains.len());
+
+			let jobs: Vec<(&Domain, &Box<dyn SubdomainModule>)> = domains
+				.iter()
+				.flat_map(|domain| subdomain_modules.iter().map(move |module| (*domain, module)))
+				.collect();
+
+			let subs: Vec<String> = stream::iter(jobs)
+				.map(|(domain, module)| async move {
+					match module.enumerate(domain.as_str()).await {
+						// Normalise the results and ensure all are subdomains of the target
+						Ok(new_subdomains) => Some(
+							new_subdomains
+								.into_iter()
+								.filter_map(|subdomain| Domain::parse(&subdomain)
+									.inspect_err(|err| log::debug!("{}: {}", module.name(), err))
+									.ok()
+								)
+								.filter(|subdomain| subdomain.is_subdomain_of(domain))
+								.map(|subdomain| subdomain.to_string())
+								.collect::<Vec<String>>()
+						),
+						Err(err) => {
+							log::error!("{}: {}: {}", module.name(), domain, err);
 							None
 						}
 					}
@@ -70,58 +183,95 @@ pub fn scan(target: &str, ports: u16, enumerate: bool, vuln: bool) -> Result<(),
 				.await
 				.into_iter()
 				.flatten()
-				.collect();
-			
-			subs.push(target.to_string());		
-			
-			// Remove duplicates via Hashset
-			// Ensure all are subdomains of the target
-			subdomains = HashSet::<String>::from_iter(subs.into_iter())
-				.into_iter()
-				.filter(|domain| domain.contains(target))
-				.map(|domain| Subdomain {
-					domain,
-					open_ports: vec![],
-				})
+				.filter(|subdomain| in_scope(scope, "enumeration", subdomain))
 				.collect();
 
-			log::info!("Found {} unique subdomains\n", subdomains.len());
+			hosts.extend(subs);
+		}
 
-		} else {
-			// If not enumerating, use the target as the only subdomain
-			subdomains = vec![Subdomain { domain: target.to_string(), open_ports: vec![] }];
+		// Remove duplicates via Hashset, targets may overlap each other
+		let mut seen = HashSet::new();
+		hosts.retain(|host| seen.insert(host.clone()));
+		let subdomains: Vec<Subdomain> = hosts
+			.into_iter()
+			.map(|domain| {
+				// IP addresses are their own address, domains get theirs from DNS
+				let addresses = domain.parse::<IpAddr>().into_iter().collect();
+				Subdomain { domain, addresses, open_ports: vec![] }
+			})
+			.collect();
+
+		if enumerate {
+			log::info!("Found {} unique hosts\n", subdomains.len());
 		}
 
+		// IP addresses are host records already, only domains need to resolve
+		let (ip_hosts, domains): (Vec<Subdomain>, Vec<Subdomain>) = subdomains
+			.into_iter()
+			.partition(|subdomain| subdomain.domain.parse::<IpAddr>().is_ok());
+
 		log::info!("Started DNS resolution");
 
 		// Resolve subdomains
-		let subdomains: Vec<Subdomain> = stream::iter(subdomains.into_iter())
+		let mut subdomains: Vec<Subdomain> = stream::iter(domains)
 			.map(|domain| dns::resolves(&dns_resolver, domain))
 			.buffer_unordered(dns_concurrency)
 			.filter_map(|domain| async move { domain })
+			.filter(|subdomain| futures::future::ready(match scope.check_addresses(&subdomain.addresses) {
+				Ok(()) => true,
+				Err(reason) => {
+					scope::dropped("DNS resolution", &subdomain.domain, &format!("resolves to {}", reason));
+					false
+				}
+			}))
 			.collect()
 			.await;
 
 		log::info!("Resolved {} subdomains\n", subdomains.len());
+		subdomains.extend(ip_hosts);
 
 		// Scan ports
-		log::info!("Started port scan");
-		
-		let subdomains: Vec<Subdomain> = stream::iter(subdomains.into_iter())
-			.map(|domain| {
-				log::info!("\tScanning {}", domain.domain);
-				ports::scan_ports(domain, ports, port_concurrency)
-			})
-			.buffer_unordered(3)
-			.collect()
-			.await;
+		let subdomains: Vec<Subdomain> = if passive {
+			log::info!("Passive scan, skipping the port scan");
+			subdomains
+		} else {
+			log::info!("Started port scan");
+			stream::iter(subdomains)
+				.map(|domain| {
+					log::info!("\tScanning {}", domain.domain);
+					ports::scan_ports(domain, &ports, port_concurrency, port_timeout)
+				})
+				.buffer_unordered(host_concurrency)
+				.collect()
+				.await
+		};
+
+		// Hosts finish in any order, sort them so the report of every target is together:
+		// domains by parent domain, then IP addresses in numerical order
+		let mut subdomains = subdomains;
+		subdomains.sort_by_key(|subdomain| match subdomain.domain.parse::<IpAddr>() {
+			Ok(ip) => (1, String::new(), Some(ip)),
+			Err(_) => (0, subdomain.domain.split('.').rev().collect::<Vec<&str>>().join("."), None),
+		});
 
-		println!();
-		log::info!("Port scan completed
+		// With --json, stdout only holds the findings
+		if !json {
+			println!();
+		}
+		if !passive {
+			log::info!("Port scan completed
 			 Found {} open ports\n", subdomains.iter().fold(0, |acc, subdomain| acc + subdomain.open_ports.len()));
-		
-		for subdomain in &subdomains {
+		}
+
+		for subdomain in subdomains.iter().filter(|_| !json) {
 			println!("{}:", subdomain.domain);
+			// Without a port scan, what is known of a host is where it points to
+			if passive {
+				for address in &subdomain.addresses {
+					println!("\t- Address {}", address);
+				}
+				continue;
+			}
 			if subdomain.open_ports.is_empty() {
 				println!("\tNo open ports");
 				continue;
@@ -135,27 +285,64 @@ pub fn scan(target: &str, ports: u16, enumerate: bool, vuln: bool) -> Result<(),
 			// Scan for vulnerabilities via http modules
 			log::info!("Started vulnerability scan");
 
-			let mut targets: Vec<(Box<dyn HttpModule>, String)> = vec![];
-			for subdomain in subdomains {
-				for port in subdomain.open_ports {
-					let http_modules = modules::get_http_modules();
-					for module in http_modules {
-						let target = format!("http://{}:{}", &subdomain.domain, port.port);
-						targets.push((module, target));
+			// One context per open port that a module runs on, the usual TLS ports are reached over HTTPS
+			let cache = Arc::new(ResponseCache::default());
+			let shared_config = Arc::new(config.clone());
+			let mut contexts = vec![];
+			for subdomain in &subdomains {
+				for port in &subdomain.open_ports {
+					let scheme = match TLS_PORTS.contains(&port.port) {
+						true => Protocol::Https,
+						false => Protocol::Http,
+					};
+					if !http_modules.iter().any(|module| module.metadata().runs_on(scheme, port.port)) {
+						continue;
+					}
+					let context = ScanContext::new(&subdomain.domain, port.address, port.port, scheme, http_client.clone(), cache.clone(), shared_config.clone());
+					let url = context.base_url();
+					if let Err(reason) = Url::parse(&url).map_err(|err| err.to_string()).and_then(|url| scope.check_url(&url)) {
+						scope::dropped("HTTP modules", &url, &reason);
+						continue;
 					}
+					contexts.push(context);
 				}
 			}
 
-			stream::iter(targets.into_iter())
-				.for_each_concurrent(vuln_concurrency,|(module, target)| {
-					let http_client = http_client.clone();
-					async move {
-						match module.scan(&http_client, &target).await {
-							Ok(Some(finding)) => println!("{:?}", &finding),
-							Ok(None) => {}
-							Err(err) => log::debug!("Error: {}", err),
-						};
+			// Technologies are known before the modules run, `/` is then in the cache for them
+			let contexts: Vec<Arc<ScanContext>> = stream::iter(contexts)
+				.map(|mut context| async move {
+					context.detect_technologies().await;
+					if !context.technologies.is_empty() {
+						log::info!("\t{} ({}) runs {}", context.base_url(), context.ip, context.technologies.join(", "));
+					}
+					Arc::new(context)
+				})
+				.buffer_unordered(vuln_concurrency)
+				.collect()
+				.await;
+
+			let mut jobs: Vec<(Arc<dyn HttpModule>, Arc<ScanContext>)> = vec![];
+			for context in &contexts {
+				for module in &http_modules {
+					// Only the modules that run on this port and protocol
+					if module.metadata().runs_on(context.scheme, context.port) {
+						jobs.push((module.clone(), context.clone()));
 					}
+				}
+			}
+
+			stream::iter(jobs)
+				.for_each_concurrent(vuln_concurrency, |(module, context)| async move {
+					match module.scan(&context).await {
+						Ok(findings) => findings.iter().for_each(|finding| match json {
+							true => match serde_json::to_string(finding) {
+								Ok(line) => println!("{}", line),
+								Err(err) => log::error!("{}: {}", module.name(), err),
+							},
+							false => println!("{}", finding),
+						}),
+						Err(err) => log::debug!("{}: {} ({}): {}", module.name(), context.base_url(), context.ip, err),
+					};
 				})
 				.await;
 		}
@@ -165,4 +352,47 @@ pub fn scan(target: &str, ports: u16, enumerate: bool, vuln: bool) -> Result<(),
 	log::info!("Scan completed in {:?}", scan_start.elapsed());
 
 	Ok(())
-}
\ No newline at end of file
+}
+
+// Keep the hosts that are in scope, logging the others
+fn in_scope(scope: &Scope, stage: &str, host: &str) -> bool {
+	match scope.check_host(host) {
+		Ok(()) => true,
+		Err(reason) => {
+			scope::dropped(stage, host, &reason);
+			false
+		}
+	}
+}
+
+// Follow redirects like reqwest does by default, as long as they stay in scope
+fn scoped_redirects(scope: Scope) -> redirect::Policy {
+	redirect::Policy::custom(move |attempt| {
+		if attempt.previous().len() >= 10 {
+			return attempt.error("too many redirects");
+		}
+		match scope.check_url(attempt.url()) {
+			Ok(()) => attempt.follow(),
+			Err(reason) => {
+				scope::dropped("HTTP redirect", attempt.url().as_str(), &reason);
+				attempt.stop()
+			}
+		}
+	})
+}
+
+// Drop the disabled modules and hand their options to the others
+fn configured<M: Module + ?Sized>(modules: Vec<Box<M>>, config: &Config) -> Result<Vec<Box<M>>, Error> {
+	let mut enabled = vec![];
+	for mut module in modules {
+		let name = module.name();
+		if !config.module_enabled(&name) {
+			continue;
+		}
+		if let Some(options) = config.module_options(&name) {
+			module.configure(options)?;
+		}
+		enabled.push(module);
+	}
+	Ok(enabled)
+}
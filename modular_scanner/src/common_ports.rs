@@ -64,4 +64,6 @@ pub const MOST_COMMON_PORTS: &[u16] = &[
     8686, 3981, 9988, 1163, 4164, 3820, 6481, 3731, 40000, 2710, 3852, 3849, 3853, 5081, 8097,
     3944, 1287, 3863, 4555, 4430, 7744, 1812, 7913, 1166, 1164, 1165, 10160, 8019, 4658, 7878,
     1259, 1092, 10008, 3304, 3307,
-];
\ No newline at end of file
+];
+// Ports that usually speak TLS, HTTP modules reach them over HTTPS
+pub const TLS_PORTS: &[u16] = &[443, 4443, 8443, 9443, 10443];
@@ -0,0 +1,275 @@
+//! Layered configuration of the scanner.
+//!
+//! Values are looked up in order, each layer overriding the previous one:
+//!   1. Built-in defaults.
+//!   2. The user file, `$XDG_CONFIG_HOME/modular_scanner/config.toml` (`~/.config/...` by default).
+//!   3. The project file, `modular_scanner.toml` in the current directory.
+//!   4. The file given with `--config`.
+//!   5. Command line flags.
+//!
+//! Layers only need to hold the values they change, tables are merged key by key.
+//!
+//! ```toml
+//! [scan]
+//! ports = 1000
+//! http_timeout_secs = 15
+//!
+//! [modules."subdomains/web_archive"]
+//! enabled = false
+//!
+//! [modules."http/directory_listing"]
+//! path = "/static/"
+//! ```
+
+use crate::{modules, Error};
+use serde::{Deserialize, Serialize};
+use std::{
+	collections::BTreeMap,
+	env,
+	fs,
+	path::{Path, PathBuf},
+};
+use toml::{Table, Value};
+
+const PROJECT_FILE: &str = "modular_scanner.toml";
+
+/*
+Struct Declaration for the configuration
+
+Config is the effective configuration of a run.
+	- `scan`: Ports, concurrency limits and timeouts.
+	- `modules`: Settings of each module, by module name.
+
+ScanConfig holds the scan parameters.
+	- `ports`: Number of most common ports to scan.
+	- `*_concurrency`: How many subdomain modules, DNS lookups, port probes per host,
+	  hosts and HTTP module checks run at the same time.
+	- `*_timeout_*`: Timeouts of HTTP requests, DNS lookups and port connections.
+
+ModuleConfig holds the settings of a module.
+	- `enabled`: Whether the module runs during scans, `Metadata::enabled_by_default` unless set.
+	- `options`: Any other key, handed over to the module through `Module::configure`.
+*/
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+	pub scan: ScanConfig,
+	pub modules: BTreeMap<String, ModuleConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScanConfig {
+	pub ports: u16,
+	pub subdomain_concurrency: usize,
+	pub dns_concurrency: usize,
+	pub port_concurrency: usize,
+	pub host_concurrency: usize,
+	pub vuln_concurrency: usize,
+	pub http_timeout_secs: u64,
+	pub dns_timeout_secs: u64,
+	pub port_timeout_ms: u64,
+}
+
+impl Default for ScanConfig {
+	fn default() -> Self {
+		ScanConfig {
+			ports: 100,
+			subdomain_concurrency: 20,
+			dns_concurrency: 100,
+			port_concurrency: 200,
+			host_concurrency: 3,
+			vuln_concurrency: 20,
+			http_timeout_secs: 8,
+			dns_timeout_secs: 4,
+			port_timeout_ms: 2000,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleConfig {
+	#[serde(default = "enabled_by_default")]
+	pub enabled: bool,
+	#[serde(flatten)]
+	pub options: Table,
+}
+
+fn enabled_by_default() -> bool {
+	true
+}
+
+/*
+A Source is a layer the configuration was built from, shown by `config show`.
+*/
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Source {
+	Defaults,
+	File(PathBuf),
+	CommandLine,
+}
+
+impl std::fmt::Display for Source {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Source::Defaults => write!(f, "built-in defaults"),
+			Source::File(path) => write!(f, "{}", path.display()),
+			Source::CommandLine => write!(f, "command line"),
+		}
+	}
+}
+
+impl Config {
+	pub fn module_enabled(&self, name: &str) -> bool {
+		self.modules.get(name).is_none_or(|module| module.enabled)
+	}
+
+	pub fn module_options(&self, name: &str) -> Option<&Table> {
+		self.modules.get(name).map(|module| &module.options)
+	}
+}
+
+/*
+Function definitions for the configuration
+	- `load`: Merges every layer that exists and returns the configuration with the sources used.
+	- `from_layers`: Merges the given tables over the defaults.
+	- `user_file`: Path of the configuration file in the user's config directory.
+*/
+
+pub fn load(explicit: Option<&Path>, overrides: Table) -> Result<(Config, Vec<Source>), Error> {
+	let mut layers = vec![];
+	let mut sources = vec![Source::Defaults];
+
+	let files = [user_file(), Some(PathBuf::from(PROJECT_FILE))];
+	for path in files.into_iter().flatten().filter(|path| path.is_file()) {
+		layers.push(read(&path)?);
+		sources.push(Source::File(path));
+	}
+	// Unlike the implicit files, a file asked for explicitly has to exist
+	if let Some(path) = explicit {
+		layers.push(read(path)?);
+		sources.push(Source::File(path.to_path_buf()));
+	}
+	if !overrides.is_empty() {
+		layers.push(overrides);
+		sources.push(Source::CommandLine);
+	}
+
+	Ok((from_layers(layers)?, sources))
+}
+
+pub fn from_layers(layers: Vec<Table>) -> Result<Config, Error> {
+	let mut merged = Table::try_from(defaults()).map_err(|err| Error::Config(err.to_string()))?;
+	for layer in layers {
+		merge(&mut merged, layer);
+	}
+
+	let config: Config = Value::Table(merged)
+		.try_into()
+		.map_err(|err: toml::de::Error| Error::Config(err.message().to_string()))?;
+
+	for name in config.modules.keys() {
+		if !known_modules().contains(name) {
+			return Err(Error::Config(format!("unknown module `{}`", name)));
+		}
+	}
+	Ok(config)
+}
+
+pub fn user_file() -> Option<PathBuf> {
+	let config_dir = env::var_os("XDG_CONFIG_HOME")
+		.filter(|dir| !dir.is_empty())
+		.map(PathBuf::from)
+		.or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+	Some(config_dir.join("modular_scanner").join("config.toml"))
+}
+
+// Every module is listed, so `config show` tells which ones run
+fn defaults() -> Config {
+	let modules = modules::get_all_modules()
+		.into_iter()
+		.map(|(_, module)| (module.name(), ModuleConfig { enabled: module.metadata().enabled_by_default, options: Table::new() }))
+		.collect();
+	Config { scan: ScanConfig::default(), modules }
+}
+
+fn known_modules() -> Vec<String> {
+	modules::get_all_modules().into_iter().map(|(_, module)| module.name()).collect()
+}
+
+fn read(path: &Path) -> Result<Table, Error> {
+	let content = fs::read_to_string(path)
+		.map_err(|err| Error::Config(format!("{}: {}", path.display(), err)))?;
+	content
+		.parse::<Table>()
+		.map_err(|err| Error::Config(format!("{}: {}", path.display(), err.message())))
+}
+
+// Tables are merged recursively, any other value of `layer` replaces the one in `base`
+fn merge(base: &mut Table, layer: Table) {
+	for (key, value) in layer {
+		match (base.get_mut(&key), value) {
+			(Some(Value::Table(base)), Value::Table(layer)) => merge(base, layer),
+			(_, value) => {
+				base.insert(key, value);
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn table(toml: &str) -> Table {
+		toml.parse().unwrap()
+	}
+
+	#[test]
+	fn defaults_only() {
+		let config = from_layers(vec![]).unwrap();
+		assert_eq!(config.scan.ports, 100);
+		assert_eq!(config.scan.port_timeout_ms, 2000);
+		assert!(config.module_enabled("subdomains/crtsh"));
+	}
+
+	#[test]
+	fn later_layers_win() {
+		let user = table("[scan]\nports = 1000\nhttp_timeout_secs = 15\n");
+		let project = table("[scan]\nports = 10\n[modules.\"subdomains/web_archive\"]\nenabled = false\n");
+		let flags = table("[scan]\ndns_concurrency = 5\n");
+		let config = from_layers(vec![user, project, flags]).unwrap();
+
+		assert_eq!(config.scan.ports, 10);
+		assert_eq!(config.scan.http_timeout_secs, 15);
+		assert_eq!(config.scan.dns_concurrency, 5);
+		assert_eq!(config.scan.vuln_concurrency, 20);
+		assert!(!config.module_enabled("subdomains/web_archive"));
+		assert!(config.module_enabled("subdomains/crtsh"));
+	}
+
+	#[test]
+	fn module_options() {
+		let config = from_layers(vec![table("[modules.\"http/directory_listing\"]\npath = \"/static/\"\n")]).unwrap();
+		let options = config.module_options("http/directory_listing").unwrap();
+		assert_eq!(options.get("path").and_then(Value::as_str), Some("/static/"));
+		assert!(config.module_enabled("http/directory_listing"));
+	}
+
+	#[test]
+	fn invalid_layers() {
+		assert!(from_layers(vec![table("[scan]\nport = 10\n")]).is_err());
+		assert!(from_layers(vec![table("[scan]\nports = \"many\"\n")]).is_err());
+		assert!(from_layers(vec![table("[modules.nope]\nenabled = false\n")]).is_err());
+	}
+
+	#[test]
+	fn show_round_trips() {
+		let config = from_layers(vec![table("[modules.\"http/directory_listing\"]\npath = \"/static/\"\n")]).unwrap();
+		let shown = toml::to_string_pretty(&config).unwrap();
+		let again = from_layers(vec![table(&shown)]).unwrap();
+		assert_eq!(again.module_options("http/directory_listing"), config.module_options("http/directory_listing"));
+	}
+}
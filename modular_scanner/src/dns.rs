@@ -9,21 +9,23 @@ use trust_dns_resolver::{
 pub type Resolver = Arc<AsyncResolver<GenericConnection, GenericConnectionProvider<TokioRuntime>>>;
 
 
-// Check if the domain resolves using DNS
-pub async fn resolves(dns_resolver: &Resolver, domain: Subdomain) -> Option<Subdomain> {
-	if dns_resolver.lookup_ip(domain.domain.as_str()).await.is_ok() {
-		return Some(domain);
+// Check if the domain resolves using DNS, keeping the addresses it resolves to
+pub async fn resolves(dns_resolver: &Resolver, mut domain: Subdomain) -> Option<Subdomain> {
+	let lookup = dns_resolver.lookup_ip(domain.domain.as_str()).await.ok()?;
+	domain.addresses = lookup.iter().collect();
+	if domain.addresses.is_empty() {
+		return None;
 	}
-	None
+	Some(domain)
 }
 
 // Create a new DNS resolver
-pub fn new_resolver() -> Resolver {
+pub fn new_resolver(timeout: Duration) -> Resolver {
 	let resolver = AsyncResolver::tokio(
 		ResolverConfig::default(),
 		{
 			let mut opts  = ResolverOpts::default();
-			opts.timeout = Duration::from_secs(4);
+			opts.timeout = timeout;
 			opts
 		},
 	)
@@ -0,0 +1,192 @@
+//! Domain names, normalised and checked against the Public Suffix List.
+//!
+//! A `Domain` is lowercased, without trailing dot or port, and internationalised names are
+//! converted to punycode, so `Bücher.Example.` and `xn--bcher-kva.example` are the same domain.
+//! The Public Suffix List is bundled in `data/public_suffix_list.dat`, it tells where the part of
+//! a name that can be registered starts: `www.example.co.uk` belongs to `example.co.uk` while
+//! `a.github.io` and `b.github.io` belong to different owners. To update it, replace the file
+//! with https://publicsuffix.org/list/public_suffix_list.dat.
+
+use crate::Error;
+use std::{collections::HashSet, fmt, net::IpAddr, sync::OnceLock};
+
+const PUBLIC_SUFFIX_LIST: &str = include_str!("../data/public_suffix_list.dat");
+
+/*
+Struct Declaration for domains
+
+Domain is a valid, normalised domain name in ASCII.
+
+Rules holds the rules of the Public Suffix List, in punycode.
+	- `suffixes`: Public suffixes, `co.uk`.
+	- `wildcards`: Domains whose every child is a public suffix, `kawasaki.jp` for `*.kawasaki.jp`.
+	- `exceptions`: Exceptions to the wildcards, `city.kawasaki.jp` for `!city.kawasaki.jp`.
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Domain(String);
+
+#[derive(Debug, Default)]
+struct Rules {
+	suffixes: HashSet<String>,
+	wildcards: HashSet<String>,
+	exceptions: HashSet<String>,
+}
+
+impl Domain {
+	pub fn parse(input: &str) -> Result<Domain, Error> {
+		let invalid = |reason: &str| Error::InvalidTarget(format!("{} ({})", input, reason));
+
+		let host = input.trim();
+		let host = match host.rsplit_once(':') {
+			Some((host, port)) if port.parse::<u16>().is_ok() => host,
+			_ => host,
+		};
+		let host = host.strip_suffix('.').unwrap_or(host);
+		if host.parse::<IpAddr>().is_ok() {
+			return Err(invalid("an IP address, not a domain"));
+		}
+
+		let domain = idna::domain_to_ascii(host).map_err(|_| invalid("invalid international name"))?;
+		let valid_label = |label: &str| {
+			!label.is_empty()
+				&& label.len() <= 63
+				&& !label.starts_with('-')
+				&& !label.ends_with('-')
+				&& label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+		};
+		if domain.len() > 253 || !domain.contains('.') || !domain.split('.').all(valid_label) {
+			return Err(invalid("not a valid domain"));
+		}
+		Ok(Domain(domain))
+	}
+
+	pub fn as_str(&self) -> &str {
+		&self.0
+	}
+
+	// The public suffix of the domain, its last label when no rule matches
+	pub fn suffix(&self) -> &str {
+		let rules = rules();
+		let mut candidate = self.as_str();
+		loop {
+			let parent = candidate.split_once('.').map(|(_, parent)| parent);
+			if rules.exceptions.contains(candidate) {
+				// An exception is registrable, its parent is the suffix
+				return parent.unwrap_or(candidate);
+			}
+			if rules.suffixes.contains(candidate) || parent.is_some_and(|parent| rules.wildcards.contains(parent)) {
+				return candidate;
+			}
+			match parent {
+				Some(parent) => candidate = parent,
+				None => return candidate,
+			}
+		}
+	}
+
+	// The suffix and one more label, `None` for a public suffix itself
+	pub fn registrable(&self) -> Option<&str> {
+		let suffix = self.suffix();
+		let prefix = self.as_str().strip_suffix(suffix)?.strip_suffix('.')?;
+		let label_start = prefix.rfind('.').map_or(0, |dot| dot + 1);
+		Some(&self.as_str()[label_start..])
+	}
+
+	pub fn is_public_suffix(&self) -> bool {
+		self.registrable().is_none()
+	}
+
+	// A strict subdomain with the same owner, so `notexample.com` and `example.com.evil.net`
+	// are not subdomains of `example.com`, nor `a.github.io` of `github.io`
+	pub fn is_subdomain_of(&self, parent: &Domain) -> bool {
+		self.as_str().ends_with(&format!(".{}", parent.as_str()))
+			&& parent.registrable().is_some()
+			&& self.registrable() == parent.registrable()
+	}
+}
+
+impl fmt::Display for Domain {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.0)
+	}
+}
+
+// The list is parsed once, on first use
+fn rules() -> &'static Rules {
+	static RULES: OnceLock<Rules> = OnceLock::new();
+	RULES.get_or_init(|| parse_rules(PUBLIC_SUFFIX_LIST))
+}
+
+fn parse_rules(list: &str) -> Rules {
+	let mut rules = Rules::default();
+	for line in list.lines() {
+		// Rules end at the first whitespace, lines starting with // are comments
+		let Some(rule) = line.split_whitespace().next().filter(|rule| !rule.starts_with("//")) else {
+			continue;
+		};
+		let (set, rule) = if let Some(rule) = rule.strip_prefix('!') {
+			(&mut rules.exceptions, rule)
+		} else if let Some(rule) = rule.strip_prefix("*.") {
+			(&mut rules.wildcards, rule)
+		} else {
+			(&mut rules.suffixes, rule)
+		};
+		match idna::domain_to_ascii(rule) {
+			Ok(rule) => set.insert(rule),
+			Err(_) => continue,
+		};
+	}
+	rules
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn domain(input: &str) -> Domain {
+		Domain::parse(input).unwrap()
+	}
+
+	#[test]
+	fn normalise() {
+		assert_eq!(domain("WWW.Example.COM.").as_str(), "www.example.com");
+		assert_eq!(domain("www.example.com:8443").as_str(), "www.example.com");
+		assert_eq!(domain("Bücher.example").as_str(), "xn--bcher-kva.example");
+		assert_eq!(domain("xn--bcher-kva.example"), domain("bücher.example"));
+		assert_eq!(domain("_dmarc.example.com").as_str(), "_dmarc.example.com");
+	}
+
+	#[test]
+	fn invalid() {
+		for input in ["", "localhost", "exa mple.com", "-bad.example.com", "example..com", "10.0.0.1", "a.b:c"] {
+			assert!(Domain::parse(input).is_err(), "{}", input);
+		}
+	}
+
+	#[test]
+	fn suffixes() {
+		assert_eq!(domain("www.example.com").suffix(), "com");
+		assert_eq!(domain("www.example.co.uk").suffix(), "co.uk");
+		assert_eq!(domain("www.example.co.uk").registrable(), Some("example.co.uk"));
+		assert_eq!(domain("a.b.foo.kawasaki.jp").registrable(), Some("b.foo.kawasaki.jp"));
+		assert_eq!(domain("www.city.kawasaki.jp").registrable(), Some("city.kawasaki.jp"));
+		assert_eq!(domain("shop.公司.cn").registrable(), Some("shop.xn--55qx5d.cn"));
+		// Unknown top level domains are suffixes too
+		assert_eq!(domain("host.corp.internal").registrable(), Some("corp.internal"));
+		assert!(domain("co.uk").is_public_suffix());
+		assert!(domain("github.io").is_public_suffix());
+	}
+
+	#[test]
+	fn subdomains() {
+		let example = domain("example.com");
+		assert!(domain("www.example.com").is_subdomain_of(&example));
+		assert!(domain("a.b.EXAMPLE.com").is_subdomain_of(&example));
+		assert!(!domain("example.com").is_subdomain_of(&example));
+		assert!(!domain("notexample.com").is_subdomain_of(&example));
+		assert!(!domain("example.com.evil.net").is_subdomain_of(&example));
+		assert!(!domain("a.github.io").is_subdomain_of(&domain("github.io")));
+		assert!(domain("www.a.github.io").is_subdomain_of(&domain("a.github.io")));
+	}
+}
@@ -15,6 +15,14 @@ pub enum Error {
 	InvalidHttpResponse(String),
 	#[error("Tokio join error: {0}")]
 	TokioJoinError(String),
+	#[error("Config: {0}")]
+	Config(String),
+	#[error("Invalid target: {0}")]
+	InvalidTarget(String),
+	#[error("Invalid scope: {0}")]
+	InvalidScope(String),
+	#[error("Io: {0}")]
+	Io(String),
 }
 
 impl std::convert::From<reqwest::Error> for Error {
@@ -23,6 +31,12 @@ impl std::convert::From<reqwest::Error> for Error {
 	}
 }
 
+impl std::convert::From<std::io::Error> for Error {
+	fn from(err: std::io::Error) -> Self {
+		Error::Io(err.to_string())
+	}
+}
+
 impl std::convert::From<tokio::task::JoinError> for Error {
 	fn from(err: tokio::task::JoinError) -> Self {
 		Error::TokioJoinError(err.to_string())
@@ -1,7 +1,10 @@
 use std::env;
 
+use std::path::Path;
+
 use anyhow::Result;
-use clap::{Arg, Command, value_parser};
+use clap::{Arg, ArgMatches, Command, value_parser};
+use toml::{Table, Value};
 
 mod error;
 pub use error::Error;
@@ -10,6 +13,90 @@ mod ports;
 mod common_ports;
 mod dns;
 mod cli;
+mod config;
+mod targets;
+mod scope;
+mod domain;
+mod selection;
+
+// Flags that override a value of the [scan] table of the configuration
+const SCAN_OVERRIDES: &[(&str, &str, &str)] = &[
+	("ports", "ports", "Number of ports to scan"),
+	("subdomain-concurrency", "subdomain_concurrency", "Subdomain modules run at the same time"),
+	("dns-concurrency", "dns_concurrency", "DNS lookups run at the same time"),
+	("port-concurrency", "port_concurrency", "Ports probed at the same time on each host"),
+	("host-concurrency", "host_concurrency", "Hosts port scanned at the same time"),
+	("vuln-concurrency", "vuln_concurrency", "HTTP module checks run at the same time"),
+	("http-timeout", "http_timeout_secs", "Timeout of HTTP requests, in seconds"),
+	("dns-timeout", "dns_timeout_secs", "Timeout of DNS lookups, in seconds"),
+	("port-timeout", "port_timeout_ms", "Timeout of port connections, in milliseconds"),
+];
+
+// --modules and --exclude-modules, shared by `modules`, `scan` and `config show`
+fn selection_args(command: Command<'static>) -> Command<'static> {
+	command
+		.arg(Arg::new("modules")
+			.help("Only run these modules: names, globs (http/*) or tags (tag:osint), comma separated")
+			.short('m')
+			.long("modules")
+			.takes_value(true)
+			.multiple_occurrences(true)
+		)
+		.arg(Arg::new("exclude-modules")
+			.help("Never run these modules: names, globs (http/*) or tags (tag:osint), comma separated")
+			.long("exclude-modules")
+			.takes_value(true)
+			.multiple_occurrences(true)
+		)
+}
+
+fn load_selection(matches: &ArgMatches) -> Result<selection::Selection, Error> {
+	let values = |id: &str| -> Vec<String> { matches.get_many::<String>(id).into_iter().flatten().cloned().collect() };
+	selection::Selection::parse(&values("modules"), &values("exclude-modules"))
+}
+
+// --config, the override flags and the module selection, shared by `scan` and `config show`
+fn config_args(mut command: Command<'static>) -> Command<'static> {
+	command = selection_args(command);
+	command = command.arg(Arg::new("config")
+		.help("Configuration file, applied over the user and project files")
+		.short('c')
+		.long("config")
+		.takes_value(true)
+	);
+	for (flag, _, help) in SCAN_OVERRIDES {
+		let mut arg = Arg::new(*flag)
+			.help(*help)
+			.long(flag)
+			.takes_value(true)
+			.value_parser(value_parser!(u64));
+		if *flag == "ports" {
+			arg = arg.short('p');
+		}
+		command = command.arg(arg);
+	}
+	command
+}
+
+fn load_config(matches: &ArgMatches) -> Result<(config::Config, Vec<config::Source>), Error> {
+	let mut scan = Table::new();
+	for (flag, key, _) in SCAN_OVERRIDES {
+		if let Some(value) = matches.get_one::<u64>(flag) {
+			scan.insert(key.to_string(), Value::Integer(*value as i64));
+		}
+	}
+
+	let mut overrides = Table::new();
+	if !scan.is_empty() {
+		overrides.insert("scan".to_string(), Value::Table(scan));
+	}
+	let selection = load_selection(matches)?;
+	if !selection.is_empty() {
+		overrides.insert("modules".to_string(), Value::Table(selection.overrides()));
+	}
+	let explicit = matches.get_one::<String>("config").map(Path::new);
+	config::load(explicit, overrides)
+}
 
 fn main() -> Result<()>{
 	
@@ -18,22 +105,43 @@ fn main() -> Result<()>{
 	let cli = Command::new(clap::crate_name!())
 		.version(clap::crate_version!())
 		.about("Subdomain and Port Scanner with vulnerability detection capabilities.")
-		.subcommand(Command::new("modules").about("List all modules"))
-		.subcommand(Command::new("scan")
+		.subcommand(selection_args(Command::new("modules"))
+			.about("List all modules, or the selected ones")
+			.arg(Arg::new("json")
+				.help("Print the modules as JSON")
+				.long("json")
+				.takes_value(false)
+			)
+		)
+		.subcommand(Command::new("config")
+			.about("Inspect the configuration")
+			.subcommand(config_args(Command::new("show")
+				.about("Print the effective configuration and where it comes from")
+			))
+			.arg_required_else_help(true)
+		)
+		.subcommand(config_args(Command::new("scan"))
 			.about("Scan a target")
 			.arg(Arg::new("target")
-				.help("The domain name to scan")
-				.required(true)
+				.help("A domain, URL, IP, CIDR or IP range (10.0.0.1-20) to scan, `-` reads one per line from stdin")
+				.required_unless_present("targets-file")
 				.short('d')
 				.long("domain")
 				.takes_value(true)
+				.multiple_occurrences(true)
 			)
-			.arg(Arg::new("ports")
-				.help("Number of ports to scan")
-				.short('p')
-				.long("ports")
-				.default_value("100")
-				.value_parser(value_parser!(u16))
+			.arg(Arg::new("targets-file")
+				.help("A file with one domain, URL, IP, CIDR or IP range per line, `-` for stdin")
+				.short('f')
+				.long("file")
+				.takes_value(true)
+				.multiple_occurrences(true)
+			)
+			.arg(Arg::new("scope")
+				.help("Scope file of the engagement, hosts, addresses and ports outside of it are never scanned")
+				.short('s')
+				.long("scope")
+				.takes_value(true)
 			)
 			.arg(Arg::new("vuln")
 				.help("Scan for vulnerabilities")
@@ -41,6 +149,18 @@ fn main() -> Result<()>{
 				.long("vuln")
 				.takes_value(false)
 			)
+			.arg(Arg::new("passive")
+				.help("Send nothing to the targets: only passive modules and DNS resolution run, no port scan")
+				.long("passive")
+				.takes_value(false)
+				.conflicts_with("vuln")
+			)
+			.arg(Arg::new("json")
+				.help("Print the findings as JSON lines instead of the host report")
+				.long("json")
+				.takes_value(false)
+				.requires("vuln")
+			)
 			.arg(Arg::new("enumerate")
 				.help("Enumerate subdomains")
 				.short('e')
@@ -54,20 +174,34 @@ fn main() -> Result<()>{
 	// Set up logging
 	env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
 		.format(|buf, record| {
-			println!("{} [!] {}", buf.timestamp(), record.args());
+			// Logs go to stderr, so the results and JSON on stdout can be piped
+			eprintln!("{} [!] {}", buf.timestamp(), record.args());
 			Ok(())
 		})
 		.init();
 	log::info!("Starting modular scanner...\n");
 	
-	if let Some(_) = cli.subcommand_matches("modules") {
-		cli::modules();
+	if let Some(matches) = cli.subcommand_matches("modules") {
+		cli::modules(&load_selection(matches)?, matches.is_present("json"))?;
+	} else if let Some(matches) = cli.subcommand_matches("config") {
+		if let Some(matches) = matches.subcommand_matches("show") {
+			let (config, sources) = load_config(matches)?;
+			cli::config_show(&config, &sources)?;
+		}
 	} else if let Some(matches) = cli.subcommand_matches("scan") {
-		let target = matches.get_one::<String>("target").unwrap();
-		let ports: u16 = matches.get_one::<u16>("ports").unwrap().clone();
+		let args: Vec<String> = matches.get_many::<String>("target").into_iter().flatten().cloned().collect();
+		let files: Vec<String> = matches.get_many::<String>("targets-file").into_iter().flatten().cloned().collect();
+		let targets = targets::load(&args, &files)?;
+		let (config, _) = load_config(matches)?;
+		let scope = match matches.get_one::<String>("scope") {
+			Some(path) => scope::load(Path::new(path))?,
+			None => scope::Scope::default(),
+		};
 		let enumerate = matches.is_present("enumerate");
 		let vuln = matches.is_present("vuln");
-		cli::scan(target, ports, enumerate, vuln)?;
+		let passive = matches.is_present("passive");
+		let json = matches.is_present("json");
+		cli::scan(&targets, &config, &scope, enumerate, vuln, passive, json)?;
 	}
 
 	// Return Ok
@@ -0,0 +1,168 @@
+//! What an HTTP module knows about the endpoint it scans.
+
+use crate::{config::Config, modules::Protocol, Error};
+use reqwest::{header::HeaderMap, Client, StatusCode};
+use std::{
+	collections::HashMap,
+	net::IpAddr,
+	sync::{Arc, Mutex},
+	time::Duration,
+};
+use tokio::sync::OnceCell;
+
+/*
+Struct Declaration for the scan context
+
+ScanContext is handed to HttpModule::scan, one per open port that a module runs on.
+	- `host`: The domain or IP address that is scanned.
+	- `ip`: The address the port scan found the port open on.
+	- `port`: The open port.
+	- `scheme`: The protocol the port is reached with, HTTPS on the usual TLS ports.
+	- `technologies`: What the server runs, from the headers of `/`, `nginx` or `PHP`.
+	- `client`: The HTTP client, with the redirect policy of the scan.
+	- `cache`: Responses shared by every module, so a page is only fetched once.
+	- `config`: The configuration of the scan, requests time out after `scan.http_timeout_secs`.
+
+Response is a fetched page, as kept in the cache.
+*/
+
+#[derive(Clone)]
+pub struct ScanContext {
+	pub host: String,
+	pub ip: IpAddr,
+	pub port: u16,
+	pub scheme: Protocol,
+	pub technologies: Vec<String>,
+	pub client: Client,
+	pub cache: Arc<ResponseCache>,
+	pub config: Arc<Config>,
+}
+
+#[derive(Debug)]
+pub struct Response {
+	pub status: StatusCode,
+	pub headers: HeaderMap,
+	pub body: String,
+}
+
+// Each URL is fetched once, concurrent requests for it wait for the first one. Failed requests
+// are not kept, they are tried again
+#[derive(Default)]
+pub struct ResponseCache {
+	responses: Mutex<HashMap<String, Arc<OnceCell<Arc<Response>>>>>,
+}
+
+impl ScanContext {
+	pub fn new(host: &str, ip: IpAddr, port: u16, scheme: Protocol, client: Client, cache: Arc<ResponseCache>, config: Arc<Config>) -> Self {
+		ScanContext { host: host.to_string(), ip, port, scheme, technologies: vec![], client, cache, config }
+	}
+
+	// `http://host:port`, IPv6 addresses need brackets in URLs
+	pub fn base_url(&self) -> String {
+		let scheme = match self.scheme {
+			Protocol::Http => "http",
+			Protocol::Https => "https",
+		};
+		match self.host.contains(':') {
+			true => format!("{}://[{}]:{}", scheme, self.host, self.port),
+			false => format!("{}://{}:{}", scheme, self.host, self.port),
+		}
+	}
+
+	pub fn url(&self, path: &str) -> String {
+		format!("{}{}", self.base_url(), path)
+	}
+
+	// GET a path of the endpoint, through the cache
+	pub async fn get(&self, path: &str) -> Result<Arc<Response>, Error> {
+		let url = self.url(path);
+		let cell = self.cache.responses.lock().expect("context: response cache lock").entry(url.clone()).or_default().clone();
+		let response = cell
+			.get_or_try_init(|| async {
+				let timeout = Duration::from_secs(self.config.scan.http_timeout_secs);
+				let response = self.client.get(&url).timeout(timeout).send().await?;
+				let status = response.status();
+				let headers = response.headers().clone();
+				let body = response.text().await?;
+				Ok::<_, Error>(Arc::new(Response { status, headers, body }))
+			})
+			.await?;
+		Ok(response.clone())
+	}
+
+	// Fill `technologies` from the headers of `/`, an endpoint that does not answer has none
+	pub async fn detect_technologies(&mut self) {
+		if let Ok(response) = self.get("/").await {
+			self.technologies = technologies(&response.headers);
+		}
+	}
+}
+
+// `Server: nginx/1.18.0 (Ubuntu)` is `nginx`, `X-Powered-By: PHP/8.1.2` is `PHP`
+fn technologies(headers: &HeaderMap) -> Vec<String> {
+	let mut technologies: Vec<String> = vec![];
+	for name in ["server", "x-powered-by"] {
+		for value in headers.get_all(name).iter().filter_map(|value| value.to_str().ok()) {
+			for product in value.split([',', ' ']).filter(|product| !product.is_empty() && !product.starts_with('(')) {
+				let product = product.split('/').next().unwrap_or_default().trim().to_string();
+				if !product.is_empty() && !technologies.contains(&product) {
+					technologies.push(product);
+				}
+			}
+		}
+	}
+	technologies
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use reqwest::header::HeaderValue;
+
+	fn context(host: &str, port: u16) -> ScanContext {
+		let ip = host.parse().unwrap_or(IpAddr::from([127, 0, 0, 1]));
+		ScanContext::new(host, ip, port, Protocol::Http, Client::new(), Arc::default(), Arc::default())
+	}
+
+	#[test]
+	fn urls() {
+		assert_eq!(context("example.com", 8080).url("/.git/HEAD"), "http://example.com:8080/.git/HEAD");
+		assert_eq!(context("2001:db8::1", 80).base_url(), "http://[2001:db8::1]:80");
+		let https = ScanContext { scheme: Protocol::Https, ..context("example.com", 443) };
+		assert_eq!(https.url("/"), "https://example.com:443/");
+	}
+
+	#[test]
+	fn detect() {
+		let mut headers = HeaderMap::new();
+		headers.append("server", HeaderValue::from_static("nginx/1.18.0 (Ubuntu)"));
+		headers.append("x-powered-by", HeaderValue::from_static("PHP/8.1.2"));
+		headers.append("x-powered-by", HeaderValue::from_static("Express, PHP"));
+		assert_eq!(technologies(&headers), vec!["nginx", "PHP", "Express"]);
+		assert!(technologies(&HeaderMap::new()).is_empty());
+	}
+
+	#[tokio::test]
+	async fn cache() {
+		use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener};
+
+		// A server that answers a single request, a second one would fail
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			let (mut stream, _) = listener.accept().await.unwrap();
+			let mut request = [0; 1024];
+			let _ = stream.read(&mut request).await.unwrap();
+			let response = "HTTP/1.1 200 OK\r\nServer: nginx\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello";
+			stream.write_all(response.as_bytes()).await.unwrap();
+		});
+
+		let mut context = context("127.0.0.1", port);
+		context.detect_technologies().await;
+		assert_eq!(context.technologies, vec!["nginx"]);
+
+		let response = context.get("/").await.unwrap();
+		assert_eq!(response.status, StatusCode::OK);
+		assert_eq!(response.body, "hello");
+	}
+}
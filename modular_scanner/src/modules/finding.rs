@@ -0,0 +1,157 @@
+//! Findings reported by the HTTP modules.
+
+use crate::modules::{Module, Reference, Severity};
+use serde::Serialize;
+use std::{fmt, ops::Range};
+
+// Characters of context kept on each side of a match in response excerpts
+const EXCERPT_CONTEXT: usize = 80;
+
+/*
+Struct Declaration for findings
+
+Finding is an issue found by a module.
+	- `kind`: What was found, to match on findings without comparing titles.
+	- `module`: Name of the module that found it.
+	- `target`: URL the issue was found at.
+	- `severity`, `references`: Taken from the module's Metadata.
+	- `title`: One line summary.
+	- `description`: What the issue is and why it matters.
+	- `evidence`: What shows the issue is there.
+	- `remediation`: How to fix it.
+
+Evidence holds what a module saw, every part is optional.
+	- `request`: The request that was sent, `GET http://example.com/`.
+	- `response`: An excerpt of the response around what matched.
+	- `matched`: The pattern that matched.
+
+FindingKind is an enum of everything the modules can find.
+*/
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+	pub kind: FindingKind,
+	pub module: String,
+	pub target: String,
+	pub severity: Severity,
+	pub title: String,
+	pub description: String,
+	pub evidence: Evidence,
+	pub references: Vec<Reference>,
+	pub remediation: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Evidence {
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub request: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub response: Option<String>,
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub matched: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FindingKind {
+	DirectoryListingDisclosure,
+}
+
+impl Finding {
+	// A finding with the module's severity and references, the rest is filled in by the module:
+	// `Finding { title, description, ..Finding::new(self, kind, &url) }`
+	pub fn new<M: Module + ?Sized>(module: &M, kind: FindingKind, target: &str) -> Finding {
+		let metadata = module.metadata();
+		Finding {
+			kind,
+			module: module.name(),
+			target: target.to_string(),
+			severity: metadata.severity,
+			title: String::new(),
+			description: String::new(),
+			evidence: Evidence::default(),
+			references: metadata.references,
+			remediation: String::new(),
+		}
+	}
+}
+
+impl fmt::Display for Finding {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		writeln!(f, "[{}] {}: {}", self.severity, self.title, self.target)?;
+		writeln!(f, "\tModule: {}", self.module)?;
+		if !self.description.is_empty() {
+			writeln!(f, "\t{}", self.description)?;
+		}
+		if let Some(request) = &self.evidence.request {
+			writeln!(f, "\tRequest: {}", request)?;
+		}
+		if let Some(matched) = &self.evidence.matched {
+			writeln!(f, "\tMatched: {}", matched)?;
+		}
+		if let Some(response) = &self.evidence.response {
+			writeln!(f, "\tResponse: {}", response)?;
+		}
+		if !self.remediation.is_empty() {
+			writeln!(f, "\tRemediation: {}", self.remediation)?;
+		}
+		if !self.references.is_empty() {
+			let references: Vec<String> = self.references.iter().map(ToString::to_string).collect();
+			writeln!(f, "\tReferences: {}", references.join(", "))?;
+		}
+		Ok(())
+	}
+}
+
+impl Evidence {
+	// The part of `text` around `matched`, on one line
+	pub fn excerpt(text: &str, matched: Range<usize>) -> String {
+		let start = text[..matched.start].char_indices().rev().nth(EXCERPT_CONTEXT - 1).map_or(0, |(index, _)| index);
+		let end = text[matched.end..].char_indices().nth(EXCERPT_CONTEXT).map_or(text.len(), |(index, _)| matched.end + index);
+
+		let mut excerpt = text[start..end].split_whitespace().collect::<Vec<&str>>().join(" ");
+		if start > 0 {
+			excerpt.insert_str(0, "...");
+		}
+		if end < text.len() {
+			excerpt.push_str("...");
+		}
+		excerpt
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn excerpt() {
+		let body = format!("{}<title>Index of /</title>\n\n{}", "a".repeat(100), "é".repeat(100));
+		let start = body.find("<title>").unwrap();
+		let end = body.find("</title>").unwrap() + "</title>".len();
+		let excerpt = Evidence::excerpt(&body, start..end);
+		assert_eq!(excerpt, format!("...{}<title>Index of /</title> {}...", "a".repeat(80), "é".repeat(78)));
+
+		assert_eq!(Evidence::excerpt("<title>Index of /</title>", 0..25), "<title>Index of /</title>");
+	}
+
+	#[test]
+	fn serialize() {
+		let finding = Finding {
+			kind: FindingKind::DirectoryListingDisclosure,
+			module: "http/directory_listing".to_string(),
+			target: "http://example.com:80/".to_string(),
+			severity: Severity::Medium,
+			title: "Directory listing".to_string(),
+			description: String::new(),
+			evidence: Evidence { matched: Some("<title>Index of .*</title>".to_string()), ..Evidence::default() },
+			references: vec![Reference::Cwe(548)],
+			remediation: String::new(),
+		};
+		let json = serde_json::to_value(&finding).unwrap();
+		assert_eq!(json["kind"], "directory_listing_disclosure");
+		assert_eq!(json["severity"], "medium");
+		assert_eq!(json["references"], serde_json::json!(["CWE-548"]));
+		assert_eq!(json["evidence"], serde_json::json!({ "matched": "<title>Index of .*</title>" }));
+	}
+}
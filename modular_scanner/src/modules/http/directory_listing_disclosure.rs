@@ -1,19 +1,21 @@
 use crate::{
-	modules::{HttpFinding, HttpModule, Module},
+	modules::{Evidence, Finding, FindingKind, HttpModule, Impact, Metadata, Module, Protocol, Reference, ScanContext, Severity},
 	Error,
 };
 use async_trait::async_trait;
-use reqwest::Client;
 use regex::Regex;
+use toml::Table;
 
 pub struct DirectoryListingDisclosure {
 	pub dir_regex: Regex,
+	// Path that is checked for a listing, `/` unless configured
+	pub path: String,
 }
 
 impl DirectoryListingDisclosure {
 	pub fn new() -> Self {
 		let dir_regex = Regex::new(r"<title>Index of .*</title>").unwrap();
-		Self { dir_regex }
+		Self { dir_regex, path: "/".to_string() }
 	}
 
 	async fn is_directory_listing(&self, body: String) -> Result<bool, Error> {
@@ -33,23 +35,60 @@ impl Module for DirectoryListingDisclosure {
 	fn description(&self) -> String {
 		"Checks for directory listing, which can potentially leak info".to_string()
 	}
+
+	fn metadata(&self) -> Metadata {
+		Metadata {
+			version: "1.0.0",
+			severity: Severity::Medium,
+			impact: Impact::Active,
+			enabled_by_default: true,
+			tags: vec!["disclosure", "misconfiguration"],
+			references: vec![Reference::Cwe(548)],
+			protocols: vec![Protocol::Http, Protocol::Https],
+			ports: vec![],
+		}
+	}
+
+	fn configure(&mut self, options: &Table) -> Result<(), Error> {
+		for (key, value) in options {
+			match (key.as_str(), value.as_str()) {
+				("path", Some(path)) if path.starts_with('/') => self.path = path.to_string(),
+				("path", _) => return Err(Error::Config(format!("{}: `path` must start with /", self.name()))),
+				_ => return Err(Error::Config(format!("{}: unknown option `{}`", self.name(), key))),
+			}
+		}
+		Ok(())
+	}
 }
 
 #[async_trait]
 impl HttpModule for DirectoryListingDisclosure {
-	async fn scan(&self, client: &Client, endpoint: &str) -> Result<Option<HttpFinding>, Error> {
-		let url = format!("{}/", endpoint);
-		let response = client.get(&url).send().await?;
+	async fn scan(&self, context: &ScanContext) -> Result<Vec<Finding>, Error> {
+		let url = context.url(&self.path);
+		let response = context.get(&self.path).await?;
 
-		if !response.status().is_success() {
-			return Ok(None);
+		if !response.status.is_success() {
+			return Ok(vec![]);
 		}
 
-		let body = response.text().await?;
-		if self.is_directory_listing(body).await? {
-			return Ok(Some(HttpFinding::DirectoryListingDisclosure(url)));
+		let body = &response.body;
+		if !self.is_directory_listing(body.clone()).await? {
+			return Ok(vec![]);
 		}
-		Ok(None)
+		// The body around the listing's title, as evidence
+		let excerpt = self.dir_regex.find(body).map(|matched| Evidence::excerpt(body, matched.range()));
+
+		Ok(vec![Finding {
+			title: "Directory listing enabled".to_string(),
+			description: format!("The web server lists the files under {}, including files that are not linked from the site.", self.path),
+			evidence: Evidence {
+				request: Some(format!("GET {}", url)),
+				response: excerpt.map(|excerpt| format!("{} {}", response.status, excerpt)),
+				matched: Some(self.dir_regex.to_string()),
+			},
+			remediation: "Disable directory listing in the web server configuration (`autoindex off` in nginx, `Options -Indexes` in Apache).".to_string(),
+			..Finding::new(self, FindingKind::DirectoryListingDisclosure, &url)
+		}])
 	}
 }
 
@@ -86,13 +125,56 @@ mod tests {
 		assert!(!module.is_directory_listing(body.to_string()).await.unwrap());
 	}
 
+	#[test]
+	fn configure() {
+		use crate::modules::Module;
+
+		let mut module = DirectoryListingDisclosure::new();
+		let options: toml::Table = "path = \"/static/\"".parse().unwrap();
+		module.configure(&options).unwrap();
+		assert_eq!(module.path, "/static/");
+
+		let options: toml::Table = "path = \"static\"".parse().unwrap();
+		assert!(module.configure(&options).is_err());
+		let options: toml::Table = "depth = 2".parse().unwrap();
+		assert!(module.configure(&options).is_err());
+	}
+
 	#[tokio::test]
 	async fn scan() {
+		use crate::modules::{FindingKind, Module, Protocol, ScanContext, Severity};
+		use tokio::{io::{AsyncReadExt, AsyncWriteExt}, net::TcpListener};
+
+		// A server with a listing at `/files/`, any other path is a regular page
+		let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+		let port = listener.local_addr().unwrap().port();
+		tokio::spawn(async move {
+			loop {
+				let (mut stream, _) = listener.accept().await.unwrap();
+				let mut request = [0; 1024];
+				let len = stream.read(&mut request).await.unwrap();
+				let body = match String::from_utf8_lossy(&request[..len]).starts_with("GET /files/ ") {
+					true => "<html><head><title>Index of /files/</title></head><body><a href=\"backup.zip\">backup.zip</a></body></html>",
+					false => "<html><head><title>Welcome</title></head><body></body></html>",
+				};
+				let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+				stream.write_all(response.as_bytes()).await.unwrap();
+			}
+		});
+		let context = ScanContext::new("127.0.0.1", [127, 0, 0, 1].into(), port, Protocol::Http, reqwest::Client::new(), Default::default(), Default::default());
+
 		let module = DirectoryListingDisclosure::new();
-		let client = reqwest::Client::new();
-		let endpoint = "http://example.com";
-		let result = module.scan(&client, endpoint).await;
-		assert!(result.is_ok());
-	}
+		assert!(module.scan(&context).await.unwrap().is_empty());
 
-}
\ No newline at end of file
+		let mut module = DirectoryListingDisclosure::new();
+		module.configure(&"path = \"/files/\"".parse().unwrap()).unwrap();
+		let findings = module.scan(&context).await.unwrap();
+		assert_eq!(findings.len(), 1);
+		let finding = &findings[0];
+		assert_eq!(finding.kind, FindingKind::DirectoryListingDisclosure);
+		assert_eq!(finding.severity, Severity::Medium);
+		assert_eq!(finding.target, format!("http://127.0.0.1:{}/files/", port));
+		assert_eq!(finding.evidence.request.as_deref(), Some(format!("GET http://127.0.0.1:{}/files/", port).as_str()));
+		assert!(finding.evidence.response.as_deref().unwrap().contains("<title>Index of /files/</title>"));
+	}
+}
@@ -0,0 +1,149 @@
+//! What a module declares about itself, for the CLI, reports and scheduling.
+
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/*
+Struct Declaration for module metadata
+
+Metadata describes a module.
+	- `version`: Version of the module, bumped when its checks change.
+	- `severity`: Severity of what the module finds, `info` for enumeration modules.
+	- `impact`: What the module sends to the target, see Impact.
+	- `enabled_by_default`: Whether the module runs unless the configuration enables it.
+	- `tags`: Tags the module can be selected by, `--modules tag:osint`.
+	- `references`: CWEs and URLs about what the module checks.
+	- `protocols`: Protocols the module speaks to the target, none for passive modules.
+	- `ports`: Ports the module is limited to, any open port when empty.
+
+Severity is the severity scale of findings, `Info` for what is only worth knowing.
+
+Impact tells how a module interacts with the target.
+	- `Passive`: Only queries third parties, no packet is sent to the target.
+	- `Active`: Sends ordinary requests to the target, like a browser or a crawler would.
+
+Reference points to documentation of what a module checks.
+*/
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Metadata {
+	pub version: &'static str,
+	pub severity: Severity,
+	pub impact: Impact,
+	pub enabled_by_default: bool,
+	pub tags: Vec<&'static str>,
+	pub references: Vec<Reference>,
+	pub protocols: Vec<Protocol>,
+	pub ports: Vec<u16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+	Info,
+	Medium,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Impact {
+	Passive,
+	Active,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+	Http,
+	Https,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reference {
+	Cwe(u32),
+	Url(&'static str),
+}
+
+impl Metadata {
+	// Whether the module can run against a port, reached with this protocol
+	pub fn runs_on(&self, protocol: Protocol, port: u16) -> bool {
+		self.protocols.contains(&protocol) && (self.ports.is_empty() || self.ports.contains(&port))
+	}
+}
+
+impl fmt::Display for Severity {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let severity = match self {
+			Severity::Info => "info",
+			Severity::Medium => "medium",
+		};
+		write!(f, "{}", severity)
+	}
+}
+
+impl fmt::Display for Impact {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let impact = match self {
+			Impact::Passive => "passive",
+			Impact::Active => "active",
+		};
+		write!(f, "{}", impact)
+	}
+}
+
+// References are written the way they are usually quoted: `CWE-548` or a URL
+impl fmt::Display for Reference {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Reference::Cwe(id) => write!(f, "CWE-{}", id),
+			Reference::Url(url) => write!(f, "{}", url),
+		}
+	}
+}
+
+impl Serialize for Reference {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(self)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn metadata(protocols: Vec<Protocol>, ports: Vec<u16>) -> Metadata {
+		Metadata {
+			version: "1.0.0",
+			severity: Severity::Medium,
+			impact: Impact::Active,
+			enabled_by_default: true,
+			tags: vec![],
+			references: vec![Reference::Cwe(548), Reference::Url("https://cwe.mitre.org/data/definitions/548.html")],
+			protocols,
+			ports,
+		}
+	}
+
+	#[test]
+	fn runs_on() {
+		let any_port = metadata(vec![Protocol::Http], vec![]);
+		assert!(any_port.runs_on(Protocol::Http, 8080));
+		assert!(!any_port.runs_on(Protocol::Https, 443));
+
+		let etcd = metadata(vec![Protocol::Http, Protocol::Https], vec![2379]);
+		assert!(etcd.runs_on(Protocol::Http, 2379));
+		assert!(!etcd.runs_on(Protocol::Http, 80));
+
+		// Passive modules never run against the target
+		assert!(!metadata(vec![], vec![]).runs_on(Protocol::Http, 80));
+	}
+
+	#[test]
+	fn serialize() {
+		let json = serde_json::to_value(metadata(vec![Protocol::Http], vec![])).unwrap();
+		assert_eq!(json["severity"], "medium");
+		assert_eq!(json["impact"], "active");
+		assert_eq!(json["references"], serde_json::json!(["CWE-548", "https://cwe.mitre.org/data/definitions/548.html"]));
+		assert!(Severity::Medium > Severity::Info);
+	}
+}
@@ -1,10 +1,19 @@
 use crate::Error;
 use async_trait::async_trait;
-use reqwest::Client;
+use serde::Serialize;
+use std::net::IpAddr;
+use toml::Table;
 
+mod context;
+mod finding;
 mod http;
+mod metadata;
 mod subdomains;
 
+pub use context::{ResponseCache, ScanContext};
+pub use finding::{Evidence, Finding, FindingKind};
+pub use metadata::{Impact, Metadata, Protocol, Reference, Severity};
+
 
 /*
 Trait Declaration for modules
@@ -13,11 +22,15 @@ A module is a struct that implements the Module trait.
 	The Module trait requires the following methods:
 		- `name`: Returns the name of the module.
 		- `description`: Returns a description of the module.
+		- `metadata`: Returns its severity, impact, tags, references and what it runs on, see Metadata.
+	It may also implement:
+		- `configure`: Takes the module's options from the configuration file. Modules without
+		  options keep the default, which rejects any option.
 
 A Module can either be a HttpModule or SubdomainModule.
 They are both #[async_trait]s.
 	HttpModule requires the following method:
-		- 'scan': Takes a reference to a HttpClient and an endpoint as &str and returns a Result<Option<HttpFinding>, Error>
+		- 'scan': Takes a reference to the ScanContext of an endpoint and returns a Result<Vec<Finding>, Error>
 	SubdomainModule requires the following method:
 		- 'enumerate': Takes a reference to a domain as &str and returns a Result<Vec<String>, Error>
 */
@@ -25,15 +38,23 @@ They are both #[async_trait]s.
 pub trait Module {
 	fn name(&self) -> String;
 	fn description(&self) -> String;
+
+	fn metadata(&self) -> Metadata;
+
+	fn configure(&mut self, options: &Table) -> Result<(), Error> {
+		match options.keys().next() {
+			Some(key) => Err(Error::Config(format!("{}: unknown option `{}`", self.name(), key))),
+			None => Ok(()),
+		}
+	}
 }
 
 #[async_trait]
 pub trait HttpModule: Module {
 	async fn scan(
 		&self,
-		client: &Client,
-		endpoint: &str,
-	) -> Result<Option<HttpFinding>, Error>;
+		context: &ScanContext,
+	) -> Result<Vec<Finding>, Error>;
 }
 
 #[async_trait]
@@ -48,53 +69,48 @@ pub trait SubdomainModule: Module {
 Struct / Enum Declaration for modules
 
 Port is a struct that represents a port.
-It has two fields:
+It has three fields:
 	- `port`: The port number.
 	- `is_open`: A boolean indicating if the port is open or not.
+	- `address`: The IP address the port was probed on.
 
 Subdomain is a struct that represents a subdomain.
-It has two fields:
+It has three fields:
 	- `domain`: The subdomain name.
+	- `addresses`: The IP addresses it resolves to, the port scan connects to the first one.
 	- `open_ports`: A vector of Port structs representing the open ports for the subdomain.
 
-HttpFinding is an enum that represents a finding from an HTTP module.
+Kind tells whether a module is a SubdomainModule or a HttpModule.
 */
 
 #[derive(Debug, Clone)]
 pub struct Port {
 	pub port: u16,
 	pub is_open: bool,
-	//pub findings: Vec<HttpFinding>,
+	pub address: IpAddr,
+	//pub findings: Vec<Finding>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Subdomain {
 	pub domain: String,
+	pub addresses: Vec<IpAddr>,
 	pub open_ports: Vec<Port>,
 }
 
-#[derive(Debug, Clone)]
-pub enum HttpFinding {
-	DirectoryListingDisclosure(String),
-	DotEnvDisclosure(String),
-	DsStoreDisclosure(String),
-	TraefikDashboardUnauthenticatedAccess(String),
-	PrometheusDashboardUnauthenticatedAccess(String),
-	KibanaUnauthenticatedAccess(String),
-	GitlabOpenRegistrations(String),
-	GitHeadDisclosure(String),
-	GitDirectoryDisclosure(String),
-	GitConfigDisclosure(String),
-	EtcdUnauthenticatedAccess(String),
-	Cve2017_9506(String),
-	Cve2018_7600(String),
-	ElasticsearchUnauthenticatedAccess(String),
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+	Subdomain,
+	Http,
 }
 
+
 /*
 Function definitions for modules
 	- `get_http_modules`: Returns a vector of all HTTP modules.
 	- `get_subdomain_modules`: Returns a vector of all subdomain modules.
+	- `get_all_modules`: Returns every module with its kind, subdomain modules first.
 */
 
 pub fn get_http_modules() -> Vec<Box<dyn HttpModule>> {
@@ -121,4 +137,14 @@ pub fn get_subdomain_modules() -> Vec<Box<dyn SubdomainModule>> {
 		Box::new(subdomains::Crtsh::new()),
 		Box::new(subdomains::WebArchive::new()),
 	]
+}
+
+pub fn get_all_modules() -> Vec<(Kind, Box<dyn Module>)> {
+	let subdomain_modules = get_subdomain_modules()
+		.into_iter()
+		.map(|module| (Kind::Subdomain, module as Box<dyn Module>));
+	let http_modules = get_http_modules()
+		.into_iter()
+		.map(|module| (Kind::Http, module as Box<dyn Module>));
+	subdomain_modules.chain(http_modules).collect()
 }
\ No newline at end of file
@@ -29,7 +29,7 @@
 /// }
 /// ```
 use crate::{
-	modules::{Module, SubdomainModule},
+	modules::{Impact, Metadata, Module, Reference, Severity, SubdomainModule},
 	Error,
 };
 use std::collections::HashSet;
@@ -68,6 +68,19 @@ impl Module for Crtsh {
 	fn description(&self) -> String {
 		"Subdomain enumeration using crt.sh".to_string()
 	}
+
+	fn metadata(&self) -> Metadata {
+		Metadata {
+			version: "1.0.0",
+			severity: Severity::Info,
+			impact: Impact::Passive,
+			enabled_by_default: true,
+			tags: vec!["osint", "certificates"],
+			references: vec![Reference::Url("https://crt.sh/")],
+			protocols: vec![],
+			ports: vec![],
+		}
+	}
 }
 
 #[async_trait]
@@ -1,5 +1,5 @@
 use crate::{
-	modules::{Module, SubdomainModule},
+	modules::{Impact, Metadata, Module, Reference, Severity, SubdomainModule},
 	Error,
 };
 use std::collections::HashSet;
@@ -27,6 +27,19 @@ impl Module for WebArchive {
 	fn description(&self) -> String {
 		"Subdomain enumeration using web.archive.org".to_string()
 	}
+
+	fn metadata(&self) -> Metadata {
+		Metadata {
+			version: "1.0.0",
+			severity: Severity::Info,
+			impact: Impact::Passive,
+			enabled_by_default: true,
+			tags: vec!["osint", "archives"],
+			references: vec![Reference::Url("https://web.archive.org/")],
+			protocols: vec![],
+			ports: vec![],
+		}
+	}
 }
 
 #[async_trait]
@@ -51,9 +64,8 @@ impl SubdomainModule for WebArchive {
 			.filter(|url| url != "original")
 			.filter_map(|url| {
 				Url::parse(&url)
-					.map_err(|err| {
+					.inspect_err(|err| {
 						log::error!("{}: error parsing url: {}: {}", self.name(), url, err);
-						err
 					})
 					.ok()
 			})
@@ -1,7 +1,4 @@
-use crate::{
-	common_ports::MOST_COMMON_PORTS, 
-	modules::{Port, Subdomain}
-};
+use crate::modules::{Port, Subdomain};
 use std::{
 	net::{SocketAddr, ToSocketAddrs},
 	time::Duration,
@@ -9,22 +6,25 @@ use std::{
 use futures::{stream, StreamExt};
 use tokio::net::TcpStream;
 
-pub async fn scan_ports(mut subdomain: Subdomain, port_size: u16, concurrency: usize) -> Subdomain {
+pub async fn scan_ports(mut subdomain: Subdomain, ports: &[u16], concurrency: usize, timeout: Duration) -> Subdomain {
 	
-	let socket_addresses: Vec<SocketAddr> = format!("{}:1024", subdomain.domain)
-		.to_socket_addrs()
-		.expect("port scanner: Creating socket address")
-		.collect();
+	// Connect to the addresses checked against the scope rather than resolving the host again
+	let socket_addresses: Vec<SocketAddr> = match subdomain.addresses.is_empty() {
+		false => subdomain.addresses.iter().map(|ip| SocketAddr::new(*ip, 1024)).collect(),
+		true => format!("{}:1024", subdomain.domain)
+			.to_socket_addrs()
+			.expect("port scanner: Creating socket address")
+			.collect(),
+	};
 
 	if socket_addresses.is_empty() {
 		return subdomain;
 	}
 
-	subdomain.open_ports = stream::iter(MOST_COMMON_PORTS.into_iter().cloned())
-		.take(port_size as usize)
+	subdomain.open_ports = stream::iter(ports.iter().copied())
 		.map(|port| {
-			let socket_address = socket_addresses[0].clone();
-			async move { scan_port(socket_address, port).await }
+			let socket_address = socket_addresses[0];
+			async move { scan_port(socket_address, port, timeout).await }
 		})
 		.buffer_unordered(concurrency)
 		.filter(|port|  futures::future::ready(port.is_open))
@@ -34,8 +34,7 @@ pub async fn scan_ports(mut subdomain: Subdomain, port_size: u16, concurrency: u
 	subdomain
 }
 
-async fn scan_port(mut socket_address: SocketAddr, port: u16) -> Port {
-	let timeout = Duration::from_secs(2);
+async fn scan_port(mut socket_address: SocketAddr, port: u16, timeout: Duration) -> Port {
 	socket_address.set_port(port);
 
 	let is_open = matches!(
@@ -43,5 +42,5 @@ async fn scan_port(mut socket_address: SocketAddr, port: u16) -> Port {
 		Ok(Ok(_)),
 	);
 
-	Port { port, is_open }
+	Port { port, is_open, address: socket_address.ip() }
 }
\ No newline at end of file
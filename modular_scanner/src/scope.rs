@@ -0,0 +1,358 @@
+//! Engagement scope, what a scan is allowed to touch.
+//!
+//! The scope file lists what is included and what is excluded, an exclusion always wins:
+//!
+//! ```toml
+//! [include]
+//! domains = ["example.com", "*.example.com", "/^api-[0-9]+\\.example\\.org$/"]
+//! ips = ["203.0.113.0/24", "198.51.100.10-20"]
+//! ports = [80, 443, "8000-8100"]
+//!
+//! [exclude]
+//! domains = ["legacy.example.com"]
+//! ips = ["203.0.113.1"]
+//! ports = [22]
+//! ```
+//!
+//! `*.example.com` matches every subdomain of example.com but not example.com itself, rules
+//! between slashes are regular expressions. Domains and IP targets have to be included, the
+//! addresses domains resolve to only have to not be excluded, and every port is in scope unless
+//! `include.ports` lists some. Without a scope file everything is in scope.
+
+use crate::{domain::Domain, targets, Error};
+use regex::Regex;
+use serde::Deserialize;
+use std::{fmt, fs, net::IpAddr, path::Path};
+use toml::Value;
+use url::Url;
+
+/*
+Struct Declaration for the scope
+
+Scope holds the rules of an engagement.
+	- `restricted`: Whether a scope file was given, hosts then have to be included.
+	- `include`: Rules a host or port has to match.
+	- `exclude`: Rules a host or port must not match.
+
+Rule is one line of the scope file.
+	- `text`: The rule as written, to tell why something was dropped.
+	- `matcher`: What the rule matches.
+*/
+
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+	restricted: bool,
+	include: Rules,
+	exclude: Rules,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Rules {
+	domains: Vec<Rule>,
+	ips: Vec<Rule>,
+	ports: Vec<Rule>,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+	text: String,
+	matcher: Matcher,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+	Domain(String),
+	Subdomains(String),
+	Regex(Regex),
+	Ips(IpAddr, IpAddr),
+	Ports(u16, u16),
+}
+
+// Layout of the scope file
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct File {
+	include: Lists,
+	exclude: Lists,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Lists {
+	domains: Vec<String>,
+	ips: Vec<String>,
+	ports: Vec<Value>,
+}
+
+impl Rule {
+	fn domain(text: &str) -> Result<Rule, Error> {
+		let invalid = |reason: String| Error::InvalidScope(format!("domain rule `{}`: {}", text, reason));
+		let matcher = if let Some(pattern) = text.strip_prefix('/').and_then(|text| text.strip_suffix('/')) {
+			Matcher::Regex(Regex::new(pattern).map_err(|err| invalid(err.to_string()))?)
+		} else if text.is_empty() || text.trim_start_matches("*.").contains('*') {
+			return Err(invalid("wildcards are only allowed as `*.domain`".to_string()));
+		} else {
+			// Rules are compared with normalised domains, international names in punycode
+			let ascii = |domain: &str| idna::domain_to_ascii(domain.trim_end_matches('.'))
+				.map_err(|_| invalid("invalid international name".to_string()));
+			match text.strip_prefix("*.") {
+				Some(parent) => Matcher::Subdomains(ascii(parent)?),
+				None => Matcher::Domain(ascii(text)?),
+			}
+		};
+		Ok(Rule { text: text.to_string(), matcher })
+	}
+
+	fn ips(text: &str) -> Result<Rule, Error> {
+		let (first, last) = match targets::parse_range(text) {
+			Ok(Some(range)) => range,
+			Ok(None) => {
+				let ip: IpAddr = text
+					.parse()
+					.map_err(|_| Error::InvalidScope(format!("IP rule `{}`: not an address, CIDR or range", text)))?;
+				(ip, ip)
+			}
+			Err(err) => return Err(Error::InvalidScope(format!("IP rule {}", err))),
+		};
+		Ok(Rule { text: text.to_string(), matcher: Matcher::Ips(first, last) })
+	}
+
+	fn ports(value: &Value) -> Result<Rule, Error> {
+		let text = match value {
+			Value::String(text) => text.clone(),
+			value => value.to_string(),
+		};
+		let invalid = || Error::InvalidScope(format!("port rule `{}`: not a port or a range of ports", text));
+		let (first, last) = text.split_once('-').unwrap_or((&text, &text));
+		let first: u16 = first.trim().parse().map_err(|_| invalid())?;
+		let last: u16 = last.trim().parse().map_err(|_| invalid())?;
+		if last < first {
+			return Err(invalid());
+		}
+		Ok(Rule { text, matcher: Matcher::Ports(first, last) })
+	}
+
+	fn matches_domain(&self, domain: &str) -> bool {
+		match &self.matcher {
+			Matcher::Domain(rule) => domain == rule,
+			Matcher::Subdomains(parent) => domain.ends_with(&format!(".{}", parent)),
+			Matcher::Regex(regex) => regex.is_match(domain),
+			_ => false,
+		}
+	}
+
+	fn matches_ip(&self, ip: IpAddr) -> bool {
+		match self.matcher {
+			Matcher::Ips(first, last) if first.is_ipv4() == ip.is_ipv4() => {
+				(targets::to_u128(first)..=targets::to_u128(last)).contains(&targets::to_u128(ip))
+			}
+			_ => false,
+		}
+	}
+
+	fn matches_port(&self, port: u16) -> bool {
+		match self.matcher {
+			Matcher::Ports(first, last) => (first..=last).contains(&port),
+			_ => false,
+		}
+	}
+}
+
+impl fmt::Display for Rule {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "`{}`", self.text)
+	}
+}
+
+/*
+Function definitions for the scope
+	- `load`: Reads a scope file.
+	- `parse`: Parses the content of a scope file.
+	- `check_domain`, `check_ip`, `check_port`: Whether a target domain, IP address or port is in
+	  scope, with the reason when it is not.
+	- `check_host`: `check_domain` or `check_ip`, for a host that can be either.
+	- `check_addresses`: Whether none of the addresses a domain resolves to is excluded.
+	- `check_url`: Whether the host and port of a URL are in scope, for HTTP requests and redirects.
+	- `dropped`: Logs what was dropped, at which stage and why.
+*/
+
+pub fn load(path: &Path) -> Result<Scope, Error> {
+	let content = fs::read_to_string(path)
+		.map_err(|err| Error::InvalidScope(format!("{}: {}", path.display(), err)))?;
+	parse(&content).map_err(|err| match err {
+		Error::InvalidScope(reason) => Error::InvalidScope(format!("{}: {}", path.display(), reason)),
+		err => err,
+	})
+}
+
+pub fn parse(content: &str) -> Result<Scope, Error> {
+	let file: File = toml::from_str(content).map_err(|err| Error::InvalidScope(err.message().to_string()))?;
+	let rules = |lists: Lists| -> Result<Rules, Error> {
+		Ok(Rules {
+			domains: lists.domains.iter().map(|text| Rule::domain(text)).collect::<Result<_, _>>()?,
+			ips: lists.ips.iter().map(|text| Rule::ips(text)).collect::<Result<_, _>>()?,
+			ports: lists.ports.iter().map(Rule::ports).collect::<Result<_, _>>()?,
+		})
+	};
+	Ok(Scope { restricted: true, include: rules(file.include)?, exclude: rules(file.exclude)? })
+}
+
+impl Scope {
+	pub fn check_domain(&self, domain: &str) -> Result<(), String> {
+		let domain = Domain::parse(domain).map_or_else(|_| domain.to_lowercase(), |domain| domain.to_string());
+		if let Some(rule) = self.exclude.domains.iter().find(|rule| rule.matches_domain(&domain)) {
+			return Err(format!("excluded by {}", rule));
+		}
+		if self.restricted && !self.include.domains.iter().any(|rule| rule.matches_domain(&domain)) {
+			return Err("not in the included domains".to_string());
+		}
+		Ok(())
+	}
+
+	pub fn check_ip(&self, ip: IpAddr) -> Result<(), String> {
+		self.check_addresses(&[ip])?;
+		if self.restricted && !self.include.ips.iter().any(|rule| rule.matches_ip(ip)) {
+			return Err("not in the included IP ranges".to_string());
+		}
+		Ok(())
+	}
+
+	pub fn check_host(&self, host: &str) -> Result<(), String> {
+		match host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+			Ok(ip) => self.check_ip(ip),
+			Err(_) => self.check_domain(host),
+		}
+	}
+
+	// Domains are in scope by name, where they point to can be shared hosting or a CDN,
+	// only the addresses that are explicitly excluded are refused
+	pub fn check_addresses(&self, addresses: &[IpAddr]) -> Result<(), String> {
+		for ip in addresses {
+			if let Some(rule) = self.exclude.ips.iter().find(|rule| rule.matches_ip(*ip)) {
+				return Err(format!("{} is excluded by {}", ip, rule));
+			}
+		}
+		Ok(())
+	}
+
+	pub fn check_port(&self, port: u16) -> Result<(), String> {
+		if let Some(rule) = self.exclude.ports.iter().find(|rule| rule.matches_port(port)) {
+			return Err(format!("excluded by {}", rule));
+		}
+		if !self.include.ports.is_empty() && !self.include.ports.iter().any(|rule| rule.matches_port(port)) {
+			return Err("not in the included ports".to_string());
+		}
+		Ok(())
+	}
+
+	pub fn check_url(&self, url: &Url) -> Result<(), String> {
+		let host = url.host_str().ok_or_else(|| "no host".to_string())?;
+		self.check_host(host)?;
+		match url.port_or_known_default() {
+			Some(port) => self.check_port(port).map_err(|reason| format!("port {} {}", port, reason)),
+			None => Err("no port".to_string()),
+		}
+	}
+}
+
+pub fn dropped(stage: &str, what: &str, reason: &str) {
+	log::warn!("Out of scope, {}: dropped {} ({})", stage, what, reason);
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const SCOPE: &str = r#"
+		[include]
+		domains = ["example.com", "*.example.com", "/^api-[0-9]+\\.example\\.org$/", "*.bücher.example"]
+		ips = ["203.0.113.0/24", "198.51.100.10-20", "2001:db8::1"]
+		ports = [80, 443, "8000-8100"]
+
+		[exclude]
+		domains = ["legacy.example.com", "*.internal.example.com"]
+		ips = ["203.0.113.1"]
+		ports = [8080]
+	"#;
+
+	fn ip(ip: &str) -> IpAddr {
+		ip.parse().unwrap()
+	}
+
+	#[test]
+	fn domains() {
+		let scope = parse(SCOPE).unwrap();
+		assert!(scope.check_domain("example.com").is_ok());
+		assert!(scope.check_domain("WWW.Example.com.").is_ok());
+		assert!(scope.check_domain("api-12.example.org").is_ok());
+		assert!(scope.check_domain("api.example.org").is_err());
+		assert!(scope.check_domain("example.org").is_err());
+		assert!(scope.check_domain("notexample.com").is_err());
+		assert_eq!(scope.check_domain("legacy.example.com"), Err("excluded by `legacy.example.com`".to_string()));
+		assert!(scope.check_domain("db.internal.example.com").is_err());
+		assert!(scope.check_domain("www.BÜCHER.example").is_ok());
+		assert!(scope.check_domain("www.xn--bcher-kva.example").is_ok());
+	}
+
+	#[test]
+	fn ips() {
+		let scope = parse(SCOPE).unwrap();
+		assert!(scope.check_ip(ip("203.0.113.200")).is_ok());
+		assert!(scope.check_ip(ip("198.51.100.20")).is_ok());
+		assert!(scope.check_ip(ip("2001:db8::1")).is_ok());
+		assert!(scope.check_ip(ip("198.51.100.21")).is_err());
+		assert!(scope.check_ip(ip("203.0.113.1")).is_err());
+		assert!(scope.check_host("[2001:db8::1]").is_ok());
+		assert!(scope.check_host("2001:db8::2").is_err());
+
+		// Resolved addresses only have to not be excluded
+		assert!(scope.check_addresses(&[ip("192.0.2.7")]).is_ok());
+		assert_eq!(
+			scope.check_addresses(&[ip("192.0.2.7"), ip("203.0.113.1")]),
+			Err("203.0.113.1 is excluded by `203.0.113.1`".to_string())
+		);
+	}
+
+	#[test]
+	fn ports() {
+		let scope = parse(SCOPE).unwrap();
+		assert!(scope.check_port(443).is_ok());
+		assert!(scope.check_port(8100).is_ok());
+		assert!(scope.check_port(8080).is_err());
+		assert!(scope.check_port(22).is_err());
+		// Every port is in scope unless some are included
+		assert!(parse("[exclude]\nports = [22]\n").unwrap().check_port(3306).is_ok());
+	}
+
+	#[test]
+	fn urls() {
+		let scope = parse(SCOPE).unwrap();
+		assert!(scope.check_url(&Url::parse("https://www.example.com/login").unwrap()).is_ok());
+		assert!(scope.check_url(&Url::parse("http://www.example.com:8080/").unwrap()).is_err());
+		assert!(scope.check_url(&Url::parse("http://evil.com/").unwrap()).is_err());
+		assert!(scope.check_url(&Url::parse("http://[2001:db8::1]:8000/").unwrap()).is_ok());
+	}
+
+	#[test]
+	fn unrestricted() {
+		let scope = Scope::default();
+		assert!(scope.check_domain("anything.test").is_ok());
+		assert!(scope.check_ip(ip("10.0.0.1")).is_ok());
+		assert!(scope.check_port(22).is_ok());
+	}
+
+	#[test]
+	fn invalid() {
+		for content in [
+			"[include]\ndomains = [\"ex*ample.com\"]\n",
+			"[include]\ndomains = [\"/(/\"]\n",
+			"[include]\nips = [\"10.0.0.0/33\"]\n",
+			"[include]\nips = [\"example.com\"]\n",
+			"[include]\nports = [\"100-10\"]\n",
+			"[include]\nports = [70000]\n",
+			"[include]\nhosts = []\n",
+		] {
+			assert!(parse(content).is_err(), "{}", content);
+		}
+	}
+}
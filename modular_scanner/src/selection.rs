@@ -0,0 +1,183 @@
+//! Selection of the modules that run, from `--modules` and `--exclude-modules`.
+//!
+//! Both flags take comma separated selectors, and can be repeated:
+//!   - a module name, `subdomains/crtsh`
+//!   - a glob on names, `http/*` (`*` matches any characters, `?` a single one)
+//!   - a tag, `tag:osint`
+//!
+//! With `--modules`, only the selected modules run, whether the configuration enables them or
+//! not. `--exclude-modules` then removes modules from what would run. The selection is applied
+//! as the `enabled` setting of the modules, the last layer of the configuration.
+
+use crate::{modules, Error};
+use regex::Regex;
+use toml::{Table, Value};
+
+/*
+Struct Declaration for module selection
+
+Selection holds the selectors of both flags.
+	- `include`: Modules that run, every module when empty.
+	- `exclude`: Modules that never run.
+
+Selector is one selector, matched against the names and tags of the modules.
+*/
+
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+	include: Vec<Selector>,
+	exclude: Vec<Selector>,
+}
+
+#[derive(Debug, Clone)]
+enum Selector {
+	Name(String, Regex),
+	Tag(String),
+}
+
+impl Selector {
+	fn parse(text: &str) -> Result<Selector, Error> {
+		if let Some(tag) = text.strip_prefix("tag:") {
+			return Ok(Selector::Tag(tag.to_string()));
+		}
+		// A glob is a regex that only knows `*` and `?`
+		let pattern: String = text
+			.chars()
+			.map(|c| match c {
+				'*' => ".*".to_string(),
+				'?' => ".".to_string(),
+				c => regex::escape(&c.to_string()),
+			})
+			.collect();
+		let regex = Regex::new(&format!("^{}$", pattern)).map_err(|err| Error::Config(err.to_string()))?;
+		Ok(Selector::Name(text.to_string(), regex))
+	}
+
+	fn matches(&self, name: &str, tags: &[&str]) -> bool {
+		match self {
+			Selector::Name(_, regex) => regex.is_match(name),
+			Selector::Tag(tag) => tags.contains(&tag.as_str()),
+		}
+	}
+}
+
+impl std::fmt::Display for Selector {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Selector::Name(text, _) => write!(f, "{}", text),
+			Selector::Tag(tag) => write!(f, "tag:{}", tag),
+		}
+	}
+}
+
+/*
+Function definitions for module selection
+	- `parse`: Parses the values of both flags, every selector has to match a module.
+	- `is_empty`: Whether no selector was given.
+	- `selects`: Whether a module with this name and these tags is selected.
+	- `overrides`: The `[modules]` settings of the selection, as a configuration layer.
+*/
+
+impl Selection {
+	pub fn parse(include: &[String], exclude: &[String]) -> Result<Selection, Error> {
+		let selectors = |values: &[String]| -> Result<Vec<Selector>, Error> {
+			values
+				.iter()
+				.flat_map(|value| value.split(','))
+				.map(str::trim)
+				.filter(|text| !text.is_empty())
+				.map(Selector::parse)
+				.collect()
+		};
+		let selection = Selection { include: selectors(include)?, exclude: selectors(exclude)? };
+
+		// A selector that matches nothing is most likely a typo
+		let known: Vec<(String, Vec<&str>)> = known_modules();
+		for selector in selection.include.iter().chain(&selection.exclude) {
+			if !known.iter().any(|(name, tags)| selector.matches(name, tags)) {
+				return Err(Error::Config(format!("no module matches `{}`, see the `modules` command", selector)));
+			}
+		}
+		Ok(selection)
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.include.is_empty() && self.exclude.is_empty()
+	}
+
+	pub fn selects(&self, name: &str, tags: &[&str]) -> bool {
+		let included = self.include.is_empty() || self.include.iter().any(|selector| selector.matches(name, tags));
+		included && !self.exclude.iter().any(|selector| selector.matches(name, tags))
+	}
+
+	pub fn overrides(&self) -> Table {
+		let mut modules = Table::new();
+		for (name, tags) in known_modules() {
+			// Without --modules, the modules that are not excluded keep their setting
+			if self.include.is_empty() && self.selects(&name, &tags) {
+				continue;
+			}
+			let mut module = Table::new();
+			module.insert("enabled".to_string(), Value::Boolean(self.selects(&name, &tags)));
+			modules.insert(name, Value::Table(module));
+		}
+		modules
+	}
+}
+
+fn known_modules() -> Vec<(String, Vec<&'static str>)> {
+	modules::get_all_modules().into_iter().map(|(_, module)| (module.name(), module.metadata().tags)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn selection(include: &[&str], exclude: &[&str]) -> Result<Selection, Error> {
+		let strings = |values: &[&str]| values.iter().map(|value| value.to_string()).collect::<Vec<String>>();
+		Selection::parse(&strings(include), &strings(exclude))
+	}
+
+	fn enabled(overrides: &Table, name: &str) -> Option<bool> {
+		overrides.get(name)?.get("enabled")?.as_bool()
+	}
+
+	#[test]
+	fn names_and_globs() {
+		let selection = selection(&["subdomains/crtsh,http/*"], &[]).unwrap();
+		assert!(selection.selects("subdomains/crtsh", &[]));
+		assert!(selection.selects("http/directory_listing", &[]));
+		assert!(!selection.selects("subdomains/web_archive", &[]));
+		assert!(!selection.selects("subdomains/crtsh.old", &[]));
+	}
+
+	#[test]
+	fn tags_and_exclusions() {
+		let selection = selection(&["tag:osint"], &["subdomains/web_*"]).unwrap();
+		let osint = ["osint"];
+		assert!(selection.selects("subdomains/crtsh", &osint));
+		assert!(!selection.selects("subdomains/web_archive", &osint));
+		assert!(!selection.selects("http/directory_listing", &[]));
+	}
+
+	#[test]
+	fn overrides() {
+		let only = selection(&["http/*"], &[]).unwrap().overrides();
+		assert_eq!(enabled(&only, "http/directory_listing"), Some(true));
+		assert_eq!(enabled(&only, "subdomains/crtsh"), Some(false));
+
+		// Excluding alone leaves the other modules to the configuration
+		let excluded = selection(&[], &["subdomains/crtsh"]).unwrap().overrides();
+		assert_eq!(enabled(&excluded, "subdomains/crtsh"), Some(false));
+		assert_eq!(enabled(&excluded, "subdomains/web_archive"), None);
+
+		assert!(selection(&[], &[]).unwrap().is_empty());
+	}
+
+	#[test]
+	fn unknown() {
+		assert!(selection(&["subdomains/nope"], &[]).is_err());
+		assert!(selection(&[], &["tag:nope"]).is_err());
+		assert!(selection(&["ftp/*"], &[]).is_err());
+	}
+}
@@ -0,0 +1,259 @@
+//! Scan targets given on the command line, in files or on stdin.
+//!
+//! Each input is a domain, a URL, an IP address, a CIDR (`10.0.0.0/24`) or a range of addresses
+//! (`10.0.0.1-20` or `10.0.0.1-10.0.1.20`). URLs are reduced to their host, paths and wildcards
+//! are dropped and domains are normalised, so the same host written two ways is only scanned
+//! once. Networks and ranges are expanded to their addresses.
+
+use crate::{domain::Domain, Error};
+use std::{
+	fmt,
+	fs,
+	io::{self, Read},
+	net::{IpAddr, Ipv4Addr, Ipv6Addr},
+};
+use url::Url;
+
+// Largest network or range that is expanded, a /16 in IPv4
+const MAX_ADDRESSES: u128 = 65536;
+
+/*
+Enum Declaration for targets
+
+Target is a host to scan.
+	- `Domain`: A domain name, subdomains can be enumerated from it. Public suffixes like
+	  `co.uk` are refused, they belong to no one.
+	- `Ip`: An IP address, which is scanned as is.
+*/
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Target {
+	Domain(Domain),
+	Ip(IpAddr),
+}
+
+impl Target {
+	pub fn parse(input: &str) -> Result<Target, Error> {
+		let invalid = || Error::InvalidTarget(input.to_string());
+		let input = input.trim();
+
+		if let Ok(ip) = input.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>() {
+			return Ok(Target::Ip(ip));
+		}
+
+		let host = if input.contains("://") {
+			let url = Url::parse(input).map_err(|_| invalid())?;
+			url.host_str().ok_or_else(invalid)?.to_string()
+		} else {
+			// host[:port][/path]
+			input.split(['/', '?', '#']).next().unwrap_or_default().to_string()
+		};
+
+		let host = host.trim_start_matches('[').trim_end_matches(']');
+		if let Ok(ip) = host.parse::<IpAddr>() {
+			return Ok(Target::Ip(ip));
+		}
+
+		let domain = Domain::parse(host.trim_start_matches("*.")).map_err(|_| invalid())?;
+		if domain.is_public_suffix() {
+			return Err(Error::InvalidTarget(format!("{} (a public suffix)", input)));
+		}
+		Ok(Target::Domain(domain))
+	}
+
+	pub fn host(&self) -> String {
+		match self {
+			Target::Domain(domain) => domain.to_string(),
+			Target::Ip(ip) => ip.to_string(),
+		}
+	}
+}
+
+impl fmt::Display for Target {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.host())
+	}
+}
+
+/*
+Function definitions for targets
+	- `load`: Parses the targets of the command line and of the target files, `-` reads stdin.
+	- `parse_lines`: Parses one target per line, skipping blank lines and # comments.
+	- `expand`: Parses a target, a CIDR or a range of addresses.
+	- `parse_range`: Parses a CIDR or a range of addresses into its first and last addresses.
+*/
+
+pub fn load(args: &[String], files: &[String]) -> Result<Vec<Target>, Error> {
+	let mut inputs = vec![];
+	let mut stdin_read = false;
+
+	let sources = args.iter().map(|arg| (arg, false)).chain(files.iter().map(|file| (file, true)));
+	for (source, is_file) in sources {
+		if source == "-" {
+			// Stdin can only be read once, `-d - -f -` is the same list
+			if !stdin_read {
+				let mut content = String::new();
+				io::stdin().read_to_string(&mut content)?;
+				inputs.push(content);
+				stdin_read = true;
+			}
+		} else if is_file {
+			inputs.push(fs::read_to_string(source)?);
+		} else {
+			inputs.push(source.clone());
+		}
+	}
+
+	let mut targets = vec![];
+	for input in &inputs {
+		targets.extend(parse_lines(input));
+	}
+	dedup(&mut targets);
+
+	if targets.is_empty() {
+		return Err(Error::InvalidTarget("no valid target given".to_string()));
+	}
+	Ok(targets)
+}
+
+pub fn parse_lines(input: &str) -> Vec<Target> {
+	let mut targets = vec![];
+	for line in input.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+		match expand(line) {
+			Ok(expanded) => targets.extend(expanded),
+			Err(err) => log::warn!("Skipping target: {}", err),
+		}
+	}
+	targets
+}
+
+pub fn expand(input: &str) -> Result<Vec<Target>, Error> {
+	let input = input.trim();
+	let Some((first, last)) = parse_range(input)? else {
+		return Ok(vec![Target::parse(input)?]);
+	};
+
+	let family = first;
+	let (first, last) = (to_u128(first), to_u128(last));
+	if last - first >= MAX_ADDRESSES {
+		return Err(Error::InvalidTarget(format!("{} (more than {} addresses)", input, MAX_ADDRESSES)));
+	}
+	Ok((first..=last).map(|address| Target::Ip(from_u128(address, family))).collect())
+}
+
+// The first and last addresses of a CIDR or a range, `None` if the input is neither
+pub fn parse_range(input: &str) -> Result<Option<(IpAddr, IpAddr)>, Error> {
+	let input = input.trim();
+	let invalid = |reason: &str| Error::InvalidTarget(format!("{} ({})", input, reason));
+
+	if let Some((address, prefix)) = input.split_once('/').filter(|(address, _)| address.parse::<IpAddr>().is_ok()) {
+		let address: IpAddr = address.parse().unwrap();
+		let (bits, max) = if address.is_ipv4() { (32, u32::MAX as u128) } else { (128, u128::MAX) };
+		let prefix: u32 = prefix.parse().ok().filter(|prefix| *prefix <= bits).ok_or_else(|| invalid("bad prefix length"))?;
+		let host_mask = max.checked_shr(prefix).unwrap_or(0);
+		let network = to_u128(address) & !host_mask;
+		Ok(Some((from_u128(network, address), from_u128(network | host_mask, address))))
+	} else if let Some((start, end)) = input.split_once('-').filter(|(start, _)| start.parse::<IpAddr>().is_ok()) {
+		let start: IpAddr = start.parse().unwrap();
+		let end = match (start, end.parse::<IpAddr>(), end.parse::<u8>()) {
+			(_, Ok(end), _) if end.is_ipv4() == start.is_ipv4() => end,
+			// a.b.c.d-e only changes the last octet
+			(IpAddr::V4(start), _, Ok(octet)) => {
+				let [a, b, c, _] = start.octets();
+				IpAddr::V4(Ipv4Addr::new(a, b, c, octet))
+			}
+			_ => return Err(invalid("bad end of range")),
+		};
+		if to_u128(end) < to_u128(start) {
+			return Err(invalid("range ends before it starts"));
+		}
+		Ok(Some((start, end)))
+	} else {
+		Ok(None)
+	}
+}
+
+pub fn to_u128(address: IpAddr) -> u128 {
+	match address {
+		IpAddr::V4(address) => u32::from(address) as u128,
+		IpAddr::V6(address) => u128::from(address),
+	}
+}
+
+// An address of the same family as `family`
+fn from_u128(address: u128, family: IpAddr) -> IpAddr {
+	match family {
+		IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::from(address as u32)),
+		IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::from(address)),
+	}
+}
+
+// Remove duplicates, keeping the first occurrence so the order of the inputs is kept
+fn dedup(targets: &mut Vec<Target>) {
+	let mut seen = std::collections::HashSet::new();
+	targets.retain(|target| seen.insert(target.clone()));
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn domain(domain: &str) -> Target {
+		Target::Domain(Domain::parse(domain).unwrap())
+	}
+
+	#[test]
+	fn normalise() {
+		assert_eq!(Target::parse("Example.COM").unwrap(), domain("example.com"));
+		assert_eq!(Target::parse("example.com.").unwrap(), domain("example.com"));
+		assert_eq!(Target::parse("*.example.com").unwrap(), domain("example.com"));
+		assert_eq!(Target::parse("https://WWW.example.com:8443/login?next=/").unwrap(), domain("www.example.com"));
+		assert_eq!(Target::parse("api.example.com:8080/v1").unwrap(), domain("api.example.com"));
+		assert_eq!(Target::parse("https://bücher.example/").unwrap(), domain("xn--bcher-kva.example"));
+		assert_eq!(Target::parse("10.0.0.1").unwrap(), Target::Ip("10.0.0.1".parse().unwrap()));
+		assert_eq!(Target::parse("http://[2001:db8::1]:80/").unwrap(), Target::Ip("2001:db8::1".parse().unwrap()));
+		assert_eq!(Target::parse("2001:db8::1").unwrap(), Target::Ip("2001:db8::1".parse().unwrap()));
+	}
+
+	#[test]
+	fn invalid() {
+		for input in ["", "localhost", "exa mple.com", "-bad.example.com", "example..com", "http://", "co.uk", "*.github.io"] {
+			assert!(Target::parse(input).is_err(), "{}", input);
+		}
+	}
+
+	fn ips(ips: &[&str]) -> Vec<Target> {
+		ips.iter().map(|ip| Target::Ip(ip.parse().unwrap())).collect()
+	}
+
+	#[test]
+	fn networks() {
+		assert_eq!(expand("192.168.1.5/30").unwrap(), ips(&["192.168.1.4", "192.168.1.5", "192.168.1.6", "192.168.1.7"]));
+		assert_eq!(expand("10.0.0.1/32").unwrap(), ips(&["10.0.0.1"]));
+		assert_eq!(expand("2001:db8::/126").unwrap(), ips(&["2001:db8::", "2001:db8::1", "2001:db8::2", "2001:db8::3"]));
+		assert_eq!(expand("10.0.0.0/16").unwrap().len(), 65536);
+		assert!(expand("10.0.0.0/15").is_err());
+		assert!(expand("2001:db8::/64").is_err());
+		assert!(expand("10.0.0.0/33").is_err());
+	}
+
+	#[test]
+	fn ranges() {
+		assert_eq!(expand("10.0.0.254-255").unwrap(), ips(&["10.0.0.254", "10.0.0.255"]));
+		assert_eq!(expand("10.0.0.255-10.0.1.1").unwrap(), ips(&["10.0.0.255", "10.0.1.0", "10.0.1.1"]));
+		assert_eq!(expand("2001:db8::ff-2001:db8::100").unwrap(), ips(&["2001:db8::ff", "2001:db8::100"]));
+		assert!(expand("10.0.0.5-4").is_err());
+		assert!(expand("10.0.0.5-256").is_err());
+		assert!(expand("10.0.0.1-2001:db8::1").is_err());
+		// Domains may contain dashes
+		assert_eq!(expand("my-host.example.com").unwrap(), vec![domain("my-host.example.com")]);
+	}
+
+	#[test]
+	fn lines() {
+		let input = "# scope\nexample.com\n\nhttps://example.com/\nnot a domain\n10.0.0.1\nEXAMPLE.com.\n";
+		let mut targets = parse_lines(input);
+		dedup(&mut targets);
+		assert_eq!(targets, vec![domain("example.com"), Target::Ip("10.0.0.1".parse().unwrap())]);
+	}
+}
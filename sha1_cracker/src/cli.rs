@@ -0,0 +1,432 @@
+use crate::{
+    cracker::{self, Crack, Limits, Outcome, Stop},
+    distributed::{self, ServerOptions},
+    extract,
+    hashes::{self, Hash},
+    identify::{self, Guess},
+    loopback, potfile, single,
+    stats::{self, Policy},
+    wordgen::{Filters, Generator},
+    Error,
+};
+use serde::Serialize;
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::Path,
+    process::ExitCode,
+    time::Instant,
+};
+
+/*
+Output is how cracked passwords are written to stdout, everything else goes to stderr.
+    - `Text`: One `Password found` line per crack.
+    - `Json`: A single document with the status, the cracks and the hashes left.
+    - `Jsonl`: One JSON object per crack.
+    - `Potfile`: One `hash:password` line per crack.
+*/
+
+#[derive(Clone, Copy)]
+pub enum Output {
+    Text,
+    Json,
+    Jsonl,
+    Potfile,
+}
+
+impl Output {
+    pub const NAMES: [&'static str; 4] = ["text", "json", "jsonl", "potfile"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(Output::Text),
+            "json" => Some(Output::Json),
+            "jsonl" => Some(Output::Jsonl),
+            "potfile" => Some(Output::Potfile),
+            _ => None,
+        }
+    }
+}
+
+/*
+Status is how a cracking run ended, it becomes the exit code of the process.
+    - `Cracked` (0): At least one password was found.
+    - `Exhausted` (1): Every candidate was tested without finding anything.
+    - `LimitReached` (3): Nothing was found before the candidate limit or the time budget.
+Errors exit with 2, like invalid arguments.
+*/
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Cracked,
+    Exhausted,
+    LimitReached,
+}
+
+impl Status {
+    fn of(outcome: &Outcome) -> Self {
+        match outcome.stop {
+            _ if !outcome.cracks.is_empty() => Status::Cracked,
+            Stop::Limit | Stop::Budget => Status::LimitReached,
+            Stop::Exhausted | Stop::AllCracked => Status::Exhausted,
+        }
+    }
+
+    pub fn code(&self) -> ExitCode {
+        match self {
+            Status::Cracked => ExitCode::SUCCESS,
+            Status::Exhausted => ExitCode::from(1),
+            Status::LimitReached => ExitCode::from(3),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Found<'a> {
+    hash: &'a str,
+    format: &'static str,
+    label: Option<String>,
+    password: &'a str,
+}
+
+#[derive(Serialize)]
+struct Summary<'a> {
+    status: Status,
+    cracked: Vec<Found<'a>>,
+    uncracked: Vec<&'a str>,
+    // Where to resume with --skip when the run was cut short
+    resume: Option<u64>,
+}
+
+pub fn crack(
+    wordlist: &str,
+    hash: &str,
+    limits: &Limits,
+    potfile: Option<&str>,
+    loopback: bool,
+    output: Output,
+) -> Result<Status, Error> {
+    let lines = load(hash)?;
+    let hashes = parse_all(&lines)?;
+    let started = Instant::now();
+    let mut outcome = attack(wordlist, &hashes, limits)?;
+
+    // A run that was cut short has nothing left for the loopback
+    if loopback && !matches!(outcome.stop, Stop::Limit | Stop::Budget) {
+        let mut seeds: Vec<String> = outcome.cracks.iter().map(|crack| crack.password.clone()).collect();
+        if let Some(potfile) = potfile {
+            seeds.extend(potfile::read(Path::new(potfile))?);
+        }
+        let remaining = Limits {
+            skip: 0,
+            limit: limits.limit.map(|limit| limit.saturating_sub(outcome.position.saturating_sub(limits.skip))),
+            budget: limits.budget.map(|budget| budget.saturating_sub(started.elapsed())),
+        };
+        let looped = loopback::crack(&lines, &outcome.cracks, seeds, &remaining)?;
+        outcome.cracks.extend(looped.cracks);
+        if matches!(looped.stop, Stop::Limit | Stop::Budget) {
+            eprintln!("Loopback stopped after {} candidates", looped.position);
+            outcome.stop = looped.stop;
+        }
+    }
+
+    if let Some(potfile) = potfile {
+        let entries = outcome
+            .cracks
+            .iter()
+            .map(|crack| (lines[crack.index].as_str(), crack.password.as_str()));
+        potfile::append(Path::new(potfile), entries)?;
+    }
+
+    report(&lines, &hashes, &outcome, output)
+}
+
+pub fn keyspace(wordlist: &str) -> Result<(), Error> {
+    let wordlist = File::open(wordlist.trim())?;
+    println!("{}", cracker::keyspace(BufReader::new(wordlist))?);
+    Ok(())
+}
+
+pub fn single(hash: &str, output: Output) -> Result<Status, Error> {
+    let lines = if Path::new(hash.trim()).is_file() {
+        hashes::read_lines(Path::new(hash.trim()))?
+    } else {
+        vec![hash.to_string()]
+    };
+
+    let mut targets = vec![];
+    for line in &lines {
+        match single::parse(line) {
+            Ok(target) => targets.push(target),
+            Err(err) => eprintln!("Skipping line: {}", err),
+        }
+    }
+    if targets.is_empty() {
+        return Err(Error::InvalidHash(hash.to_string()));
+    }
+
+    let cracks = single::crack(&targets);
+    let outcome = finished(cracks, targets.len());
+    let (lines, hashes): (Vec<String>, Vec<Box<dyn Hash>>) =
+        targets.into_iter().map(|target| (target.line, target.hash)).unzip();
+    report(&lines, &hashes, &outcome, output)
+}
+
+pub fn stats(file: &str, policy: &Policy, top: usize) -> Result<(), Error> {
+    let content = fs::read_to_string(file.trim())?;
+    let lines: Vec<&str> = content.lines().filter(|line| !line.is_empty()).collect();
+
+    // A potfile only if every line starts with a hash, plaintexts may contain colons too
+    let passwords: Vec<String> = match lines.iter().map(|line| potfile::password(line)).collect::<Option<Vec<_>>>() {
+        Some(passwords) if !passwords.is_empty() => passwords.into_iter().map(str::to_string).collect(),
+        _ => lines.iter().map(|line| line.to_string()).collect(),
+    };
+    if passwords.is_empty() {
+        println!("No passwords to analyse");
+        return Ok(());
+    }
+
+    let stats = stats::analyse(&passwords, policy);
+    let percent = |count: usize| count as f64 * 100.0 / stats.total as f64;
+    let years: usize = stats.years.iter().map(|(_, count)| count).sum();
+
+    println!("Passwords: {} ({} unique)", stats.total, stats.unique);
+    println!(
+        "Policy (length >= {}, classes >= {}): {} compliant ({:.1}%)",
+        policy.min_length,
+        policy.min_classes,
+        stats.compliant,
+        percent(stats.compliant)
+    );
+    println!(
+        "Ending with a year: {} ({:.1}%), with a date: {} ({:.1}%)",
+        years,
+        percent(years),
+        stats.dates,
+        percent(stats.dates)
+    );
+
+    println!("\nLengths");
+    for (length, count) in &stats.lengths {
+        println!("\t{:>3}: {:>7} ({:.1}%)", length, count, percent(*count));
+    }
+    for (title, list) in [
+        ("Character sets", &stats.charsets),
+        ("Masks", &stats.masks),
+        ("Base words", &stats.base_words),
+        ("Years", &stats.years),
+    ] {
+        println!("\n{}", title);
+        for (value, count) in list.iter().take(top) {
+            println!("\t{:>7} ({:>5.1}%) {}", count, percent(*count), value);
+        }
+    }
+    Ok(())
+}
+
+pub fn generate(paths: &[&String], filters: &Filters, output: Option<&str>) -> Result<(), Error> {
+    let mut generator = Generator::default();
+    for path in paths {
+        generator.add_path(Path::new(path.as_str()))?;
+    }
+
+    let words = generator.words(filters);
+    let wordlist: String = words.iter().map(|(word, _)| format!("{}\n", word)).collect();
+    match output {
+        Some(output) => {
+            fs::write(output, wordlist)?;
+            eprintln!("{} words written to {}", words.len(), output);
+        }
+        None => print!("{}", wordlist),
+    }
+    Ok(())
+}
+
+pub fn server(wordlist: &str, hash: &str, options: ServerOptions, output: Output) -> Result<Status, Error> {
+    let lines = load(hash)?;
+    let hashes = parse_all(&lines)?;
+    let cracks = distributed::serve(Path::new(wordlist.trim()), lines.clone(), options)?;
+    report(&lines, &hashes, &finished(cracks, hashes.len()), output)
+}
+
+pub fn worker(server: &str, name: &str, secret: &str) -> Result<(), Error> {
+    distributed::work(server, name, secret)
+}
+
+pub fn identify(hash: &str) -> Result<(), Error> {
+    let lines: Vec<String> = if Path::new(hash).is_file() {
+        fs::read_to_string(hash)?.lines().map(str::to_string).collect()
+    } else {
+        vec![hash.to_string()]
+    };
+
+    for line in lines.iter().map(|line| line.trim()).filter(|line| !line.is_empty()) {
+        println!("{}", line);
+        let guesses = identify::identify(line);
+        if guesses.is_empty() {
+            println!("\tUnknown format");
+        }
+        for guess in &guesses {
+            println!("{}", format_guess(guess));
+        }
+    }
+    Ok(())
+}
+
+// Returns None when only extracting, the status of the cracking run otherwise
+pub fn extract(sam: &str, system: &str, wordlist: Option<&str>, output: Output) -> Result<Option<Status>, Error> {
+    let accounts = extract::extract_sam(Path::new(sam), Path::new(system))?;
+
+    let mut lines = vec![];
+    let mut hashes = vec![];
+    for account in &accounts {
+        let line = account.pwdump();
+        // The hashes are the result unless they are cracked, then the cracks are
+        match (wordlist, output) {
+            (None, _) | (Some(_), Output::Text) => println!("{}", line),
+            _ => eprintln!("{}", line),
+        }
+        if account.nt_hash.is_some() {
+            hashes.push(hashes::parse(&line)?);
+            lines.push(line);
+        }
+    }
+
+    match wordlist {
+        Some(wordlist) if !hashes.is_empty() => {
+            if let Output::Text = output {
+                println!();
+            }
+            let outcome = attack(wordlist, &hashes, &Limits::default())?;
+            report(&lines, &hashes, &outcome, output).map(Some)
+        }
+        _ => Ok(None),
+    }
+}
+
+// The hash argument is either a single hash or a file with one hash per line,
+// returns the lines in a format we can crack
+fn load(hash: &str) -> Result<Vec<String>, Error> {
+    let hash = hash.trim();
+    let lines = if Path::new(hash).is_file() {
+        hashes::read_lines(Path::new(hash))?
+    } else {
+        vec![hash.to_string()]
+    };
+
+    let mut valid = vec![];
+    for line in lines {
+        match hashes::parse(&line) {
+            Ok(_) => valid.push(line),
+            Err(err) if Path::new(hash).is_file() => eprintln!("Skipping line: {}", err),
+            Err(err) => {
+                let guesses = identify::identify(hash);
+                if !guesses.is_empty() {
+                    eprintln!("The hash could be:");
+                    for guess in &guesses {
+                        eprintln!("{}", format_guess(guess));
+                    }
+                }
+                return Err(err);
+            }
+        }
+    }
+
+    if valid.is_empty() {
+        return Err(Error::InvalidHash(hash.to_string()));
+    }
+    Ok(valid)
+}
+
+fn parse_all(lines: &[String]) -> Result<Vec<Box<dyn Hash>>, Error> {
+    lines.iter().map(|line| hashes::parse(line)).collect()
+}
+
+fn attack(wordlist: &str, hashes: &[Box<dyn Hash>], limits: &Limits) -> Result<Outcome, Error> {
+    let wordlist = File::open(wordlist.trim())?;
+    cracker::crack(BufReader::new(&wordlist), hashes, limits)
+}
+
+// Cracks of a run that went through all of its candidates
+fn finished(cracks: Vec<Crack>, hashes: usize) -> Outcome {
+    let stop = if cracks.len() == hashes { Stop::AllCracked } else { Stop::Exhausted };
+    Outcome {
+        cracks,
+        position: 0,
+        stop,
+    }
+}
+
+fn report(lines: &[String], hashes: &[Box<dyn Hash>], outcome: &Outcome, output: Output) -> Result<Status, Error> {
+    let status = Status::of(outcome);
+
+    match output {
+        Output::Text => {
+            for crack in &outcome.cracks {
+                let hash = &hashes[crack.index];
+                match hash.label() {
+                    Some(label) => println!("Password found ({} {}): {}", hash.format(), label, crack.password),
+                    None => println!("Password found: {}", crack.password),
+                }
+            }
+        }
+        Output::Json => {
+            let mut cracked = vec![false; hashes.len()];
+            for crack in &outcome.cracks {
+                cracked[crack.index] = true;
+            }
+            let summary = Summary {
+                cracked: outcome.cracks.iter().map(|crack| found(lines, hashes, crack)).collect(),
+                uncracked: lines
+                    .iter()
+                    .zip(cracked)
+                    .filter(|(_, cracked)| !cracked)
+                    .map(|(line, _)| line.trim())
+                    .collect(),
+                resume: matches!(outcome.stop, Stop::Limit | Stop::Budget).then_some(outcome.position),
+                status,
+            };
+            println!("{}", serde_json::to_string_pretty(&summary)?);
+        }
+        Output::Jsonl => {
+            for crack in &outcome.cracks {
+                println!("{}", serde_json::to_string(&found(lines, hashes, crack))?);
+            }
+        }
+        Output::Potfile => {
+            for crack in &outcome.cracks {
+                println!("{}", potfile::line(&lines[crack.index], &crack.password));
+            }
+        }
+    }
+
+    if outcome.cracks.is_empty() {
+        eprintln!("Password not found :(");
+    } else if outcome.cracks.len() < hashes.len() {
+        eprintln!("{} hash(es) not found", hashes.len() - outcome.cracks.len());
+    }
+    match outcome.stop {
+        Stop::Limit => eprintln!("Candidate limit reached, resume with --skip {}", outcome.position),
+        Stop::Budget => eprintln!("Time budget exhausted, resume with --skip {}", outcome.position),
+        Stop::Exhausted | Stop::AllCracked => (),
+    }
+    Ok(status)
+}
+
+fn found<'a>(lines: &'a [String], hashes: &[Box<dyn Hash>], crack: &'a Crack) -> Found<'a> {
+    let hash = &hashes[crack.index];
+    Found {
+        hash: lines[crack.index].trim(),
+        format: hash.format(),
+        label: hash.label(),
+        password: &crack.password,
+    }
+}
+
+fn format_guess(guess: &Guess) -> String {
+    match guess.mode {
+        Some(mode) => format!("\t[{:>3}] {} (mode: {})", guess.score, guess.name, mode),
+        None => format!("\t[{:>3}] {} (unsupported)", guess.score, guess.name),
+    }
+}
@@ -0,0 +1,296 @@
+use crate::{
+    hashes::Hash,
+    multi_sha1::{self, Backend, DIGEST_LEN},
+    Error,
+};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    io::BufRead,
+    time::{Duration, Instant},
+};
+
+// Candidates are read and hashed this many at a time
+const BATCH_SIZE: usize = 4096;
+
+/*
+A Crack is a hash that was recovered from the wordlist.
+    - `index`: Position of the hash in the list that was given to `crack`.
+    - `password`: The candidate that produced the hash.
+*/
+
+pub struct Crack {
+    pub index: usize,
+    pub password: String,
+}
+
+/*
+Limits restricts a run to part of the keyspace, so a job can be sharded or time boxed.
+    - `skip`: Number of candidates to skip at the start of the wordlist.
+    - `limit`: Stop after testing this many candidates.
+    - `budget`: Stop once this much time has passed.
+*/
+
+#[derive(Default)]
+pub struct Limits {
+    pub skip: u64,
+    pub limit: Option<u64>,
+    pub budget: Option<Duration>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Stop {
+    Exhausted,
+    AllCracked,
+    Limit,
+    Budget,
+}
+
+/*
+Outcome is the result of a run.
+    - `cracks`: The hashes that were recovered.
+    - `position`: Index of the first candidate that was not tested, where a later run can resume.
+    - `stop`: Why the run ended.
+*/
+
+pub struct Outcome {
+    pub cracks: Vec<Crack>,
+    pub position: u64,
+    pub stop: Stop,
+}
+
+// Number of candidates in a wordlist
+pub fn keyspace(wordlist: impl BufRead) -> Result<u64, Error> {
+    candidates(wordlist).try_fold(0, |count, candidate| candidate.map(|_| count + 1))
+}
+
+// The lines of a wordlist as bytes, without their `\n` or `\r\n` ending
+fn candidates(wordlist: impl BufRead) -> impl Iterator<Item = Result<Vec<u8>, Error>> {
+    wordlist.split(b'\n').map(|line| {
+        let mut line = line?;
+        if line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        Ok(line)
+    })
+}
+
+// Every format hashes the candidate bytes as they are, which are written `$HEX[...]` when they are not UTF-8
+fn password(candidate: &[u8]) -> String {
+    match std::str::from_utf8(candidate) {
+        Ok(text) => text.to_string(),
+        Err(_) => format!("$HEX[{}]", hex::encode(candidate)),
+    }
+}
+
+// The candidate bytes back from a reported password, reading `$HEX[...]` the way `password` writes it
+pub fn candidate(password: &str) -> Cow<'_, [u8]> {
+    password
+        .strip_prefix("$HEX[")
+        .and_then(|rest| rest.strip_suffix(']'))
+        .and_then(|digits| hex::decode(digits).ok())
+        .map_or(Cow::Borrowed(password.as_bytes()), Cow::Owned)
+}
+
+// Test every candidate of the wordlist against every hash that is not cracked yet
+pub fn crack(wordlist: impl BufRead, hashes: &[Box<dyn Hash>], limits: &Limits) -> Result<Outcome, Error> {
+    let backend = Backend::detect();
+    let deadline = limits.budget.map(|budget| Instant::now() + budget);
+
+    // Unsalted SHA-1 hashes are found by looking up the first 4 bytes of each candidate digest,
+    // every other format goes through `verify`
+    let mut sha1_table: HashMap<u32, Vec<([u8; DIGEST_LEN], usize)>> = HashMap::new();
+    let mut others = vec![];
+    for (index, hash) in hashes.iter().enumerate() {
+        match hash.sha1_digest() {
+            Some(digest) => sha1_table.entry(prefix(&digest)).or_default().push((digest, index)),
+            None => others.push(index),
+        }
+    }
+
+    let mut cracked = vec![false; hashes.len()];
+    let mut cracks = vec![];
+    let mut lines = candidates(wordlist);
+
+    let mut position = 0;
+    while position < limits.skip {
+        match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        position += 1;
+    }
+    let end = limits.limit.map(|limit| position + limit);
+
+    let stop = loop {
+        if cracks.len() == hashes.len() {
+            break Stop::AllCracked;
+        }
+        if end.is_some_and(|end| position >= end) {
+            break Stop::Limit;
+        }
+        if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+            break Stop::Budget;
+        }
+
+        let size = end.map_or(BATCH_SIZE, |end| BATCH_SIZE.min((end - position) as usize));
+        let batch = lines.by_ref().take(size).collect::<Result<Vec<Vec<u8>>, _>>()?;
+        if batch.is_empty() {
+            break Stop::Exhausted;
+        }
+        let candidates: Vec<&[u8]> = batch.iter().map(Vec::as_slice).collect();
+
+        if !sha1_table.is_empty() {
+            let digests = multi_sha1::digest_batch(backend, &candidates);
+
+            for (candidate, digest) in candidates.iter().zip(digests.iter()) {
+                let Some(entries) = sha1_table.get(&prefix(digest)) else {
+                    continue;
+                };
+                for (target, index) in entries {
+                    if !cracked[*index] && target == digest {
+                        cracked[*index] = true;
+                        cracks.push(Crack {
+                            index: *index,
+                            password: password(candidate),
+                        });
+                    }
+                }
+            }
+        }
+
+        // Slow formats can take a while per batch, so the budget is also checked per candidate
+        for (tested, candidate) in candidates.iter().enumerate() {
+            if others.is_empty() {
+                break;
+            }
+            if deadline.is_some_and(|deadline| Instant::now() >= deadline) {
+                position += tested as u64;
+                return Ok(Outcome {
+                    cracks,
+                    position,
+                    stop: Stop::Budget,
+                });
+            }
+            for index in &others {
+                if !cracked[*index] && hashes[*index].verify(candidate) {
+                    cracked[*index] = true;
+                    cracks.push(Crack {
+                        index: *index,
+                        password: password(candidate),
+                    });
+                }
+            }
+        }
+
+        position += candidates.len() as u64;
+    };
+
+    Ok(Outcome { cracks, position, stop })
+}
+
+fn prefix(digest: &[u8; DIGEST_LEN]) -> u32 {
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hashes;
+    use std::io::Cursor;
+
+    const WORDLIST: &str = "123456\npassword\nqwerty\nletmein\ndragon\n";
+
+    fn sha1(password: &str) -> Box<dyn Hash> {
+        use sha1::{Digest, Sha1};
+        hashes::parse(&hex::encode(Sha1::digest(password.as_bytes()))).unwrap()
+    }
+
+    #[test]
+    fn keyspace_counts_candidates() {
+        assert_eq!(keyspace(Cursor::new(WORDLIST)).unwrap(), 5);
+        assert_eq!(keyspace(Cursor::new("a\nb")).unwrap(), 2);
+        assert_eq!(keyspace(Cursor::new("")).unwrap(), 0);
+    }
+
+    #[test]
+    fn line_endings_and_bytes() {
+        // Windows line endings, a Latin-1 `café` and spaces that are part of the password
+        let wordlist = b"123456\r\ncaf\xe9\r\n pass word \r\nletmein".to_vec();
+        assert_eq!(keyspace(Cursor::new(&wordlist)).unwrap(), 4);
+
+        let ldap = {
+            use base64::{engine::general_purpose::STANDARD, Engine};
+            use sha1::{Digest, Sha1};
+            hashes::parse(&format!("{{SHA}}{}", STANDARD.encode(Sha1::digest(b"caf\xe9")))).unwrap()
+        };
+        let raw_sha1 = {
+            use sha1::{Digest, Sha1};
+            hashes::parse(&hex::encode(Sha1::digest(b"caf\xe9"))).unwrap()
+        };
+        let hashes = vec![ldap, raw_sha1, sha1(" pass word "), sha1("letmein")];
+
+        let outcome = crack(Cursor::new(&wordlist), &hashes, &Limits::default()).unwrap();
+        assert_eq!(outcome.stop, Stop::AllCracked);
+        let mut passwords: Vec<(usize, String)> = outcome.cracks.into_iter().map(|crack| (crack.index, crack.password)).collect();
+        passwords.sort();
+        assert_eq!(
+            passwords,
+            vec![
+                (0, "$HEX[636166e9]".to_string()),
+                (1, "$HEX[636166e9]".to_string()),
+                (2, " pass word ".to_string()),
+                (3, "letmein".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn hex_passwords() {
+        assert_eq!(password(b"caf\xe9"), "$HEX[636166e9]");
+        assert_eq!(candidate("$HEX[636166e9]").as_ref(), b"caf\xe9");
+        assert_eq!(candidate("café").as_ref(), "café".as_bytes());
+        assert_eq!(candidate("$HEX[zz]").as_ref(), b"$HEX[zz]");
+    }
+
+    #[test]
+    fn skip_and_limit() {
+        let hashes = vec![sha1("password"), sha1("letmein")];
+
+        let outcome = crack(Cursor::new(WORDLIST), &hashes, &Limits::default()).unwrap();
+        assert_eq!(outcome.stop, Stop::AllCracked);
+        assert_eq!(outcome.cracks.len(), 2);
+
+        // "password" is before the range, "letmein" is inside it
+        let limits = Limits {
+            skip: 2,
+            limit: Some(2),
+            budget: None,
+        };
+        let outcome = crack(Cursor::new(WORDLIST), &hashes, &limits).unwrap();
+        assert_eq!(outcome.stop, Stop::Limit);
+        assert_eq!(outcome.position, 4);
+        assert_eq!(outcome.cracks.len(), 1);
+        assert_eq!(outcome.cracks[0].password, "letmein");
+
+        let limits = Limits {
+            skip: 4,
+            ..Limits::default()
+        };
+        let outcome = crack(Cursor::new(WORDLIST), &hashes, &limits).unwrap();
+        assert_eq!(outcome.stop, Stop::Exhausted);
+        assert_eq!(outcome.position, 5);
+        assert!(outcome.cracks.is_empty());
+    }
+
+    #[test]
+    fn budget() {
+        let limits = Limits {
+            budget: Some(Duration::ZERO),
+            ..Limits::default()
+        };
+        let outcome = crack(Cursor::new(WORDLIST), &[sha1("dragon")], &limits).unwrap();
+        assert_eq!(outcome.stop, Stop::Budget);
+        assert_eq!(outcome.position, 0);
+    }
+}
@@ -0,0 +1,99 @@
+//! Distributed cracking over TCP.
+//!
+//! The server splits the wordlist into work units (byte ranges that start and end on line
+//! boundaries) and hands them out to workers along with the hashes. Workers crack each unit and
+//! report the hits back, sending heartbeats while they work. Units of workers that disconnect
+//! or stop sending heartbeats are given to someone else.
+//!
+//! Messages are JSON objects, one per line.
+
+use crate::Error;
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+
+mod server;
+mod worker;
+
+pub use server::{serve, ServerOptions};
+pub use worker::work;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Message {
+    // Worker -> server
+    Hello { name: String, secret: String },
+    Request,
+    Progress { unit: usize },
+    Result { unit: usize, candidates: u64, cracks: Vec<Hit> },
+    // Server -> worker
+    Hashes { hashes: Vec<String>, heartbeat_secs: u64 },
+    // `data` is the base64 of the unit, wordlists are not always UTF-8
+    Unit { unit: usize, start: u64, end: u64, cracked: Vec<usize>, data: String },
+    Wait { secs: u64 },
+    Done,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Hit {
+    index: usize,
+    password: String,
+}
+
+fn send(writer: &mut impl Write, message: &Message) -> Result<(), Error> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+// Returns None once the peer closed the connection
+fn receive(reader: &mut impl BufRead) -> Result<Option<Message>, Error> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&line)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, net::TcpListener, thread, time::Duration};
+
+    #[test]
+    fn server_and_worker() {
+        let path = std::env::temp_dir().join(format!("sha1_cracker_distributed_{}", std::process::id()));
+        fs::write(&path, b"foo\ncaf\xe9\nbar\n").unwrap();
+        // SHA-1 of the Latin-1 bytes of `café`, which only cracks if the unit arrives untouched
+        let hashes = vec!["d2f52bc4406898fc722c0b4e314f9b46fc85cde4".to_string()];
+
+        let listen = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().to_string();
+        let options = ServerOptions {
+            listen: listen.clone(),
+            secret: "s3cret".to_string(),
+            unit_size: 4,
+            timeout: Duration::from_secs(60),
+        };
+        let server = {
+            let path = path.clone();
+            thread::spawn(move || serve(&path, hashes, options))
+        };
+
+        // The server may not be listening yet
+        let mut worked = work(&listen, "test", "s3cret");
+        for _ in 0..50 {
+            if worked.is_ok() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(100));
+            worked = work(&listen, "test", "s3cret");
+        }
+        worked.unwrap();
+
+        let cracks = server.join().unwrap().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(cracks.len(), 1);
+        assert_eq!(cracks[0].password, "$HEX[636166e9]");
+    }
+}
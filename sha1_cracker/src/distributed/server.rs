@@ -0,0 +1,397 @@
+use crate::{
+    cracker::{self, Crack},
+    distributed::{receive, send, Hit, Message},
+    hashes::{self, Hash},
+    Error,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{BufRead, BufReader, Read, Seek, SeekFrom},
+    net::{TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+const ACCEPT_POLL: Duration = Duration::from_millis(100);
+const WAIT_SECS: u64 = 1;
+
+/*
+ServerOptions holds the settings of the coordinator.
+    - `listen`: Address to accept workers on.
+    - `secret`: Shared secret workers have to send in their Hello before getting the hashes.
+    - `unit_size`: Approximate size of a work unit in bytes of wordlist.
+    - `timeout`: How long a unit stays assigned to a worker without news from it.
+*/
+
+pub struct ServerOptions {
+    pub listen: String,
+    pub secret: String,
+    pub unit_size: u64,
+    pub timeout: Duration,
+}
+
+struct Unit {
+    start: u64,
+    end: u64,
+}
+
+struct Assignment {
+    worker: String,
+    deadline: Instant,
+}
+
+struct State {
+    wordlist: PathBuf,
+    hashes: Vec<String>,
+    units: Vec<Unit>,
+    pending: VecDeque<usize>,
+    assigned: HashMap<usize, Assignment>,
+    completed: Vec<bool>,
+    cracks: Vec<Option<String>>,
+    timeout: Duration,
+}
+
+impl State {
+    fn new(wordlist: &Path, hashes: Vec<String>, units: Vec<Unit>, timeout: Duration) -> Self {
+        State {
+            wordlist: wordlist.to_path_buf(),
+            cracks: vec![None; hashes.len()],
+            hashes,
+            pending: (0..units.len()).collect(),
+            completed: vec![false; units.len()],
+            units,
+            assigned: HashMap::new(),
+            timeout,
+        }
+    }
+
+    fn finished(&self) -> bool {
+        self.completed.iter().all(|done| *done) || self.cracks.iter().all(Option::is_some)
+    }
+
+    fn release(&mut self, worker: &str) {
+        let units: Vec<usize> = self
+            .assigned
+            .iter()
+            .filter(|(_, assignment)| assignment.worker == worker)
+            .map(|(unit, _)| *unit)
+            .collect();
+        for unit in units {
+            self.assigned.remove(&unit);
+            self.pending.push_front(unit);
+            eprintln!("Unit {} of {} is back in the queue", unit, worker);
+        }
+    }
+
+    fn expire(&mut self) {
+        let now = Instant::now();
+        let expired: Vec<usize> = self
+            .assigned
+            .iter()
+            .filter(|(_, assignment)| assignment.deadline < now)
+            .map(|(unit, _)| *unit)
+            .collect();
+        for unit in expired {
+            if let Some(assignment) = self.assigned.remove(&unit) {
+                eprintln!("Unit {} timed out on {}, reassigning it", unit, assignment.worker);
+                self.pending.push_front(unit);
+            }
+        }
+    }
+}
+
+// Hand out the wordlist to workers until every unit is done or every hash is cracked
+pub fn serve(wordlist: &Path, hashes: Vec<String>, options: ServerOptions) -> Result<Vec<Crack>, Error> {
+    let units = split(wordlist, options.unit_size)?;
+    eprintln!("Split {} into {} units, listening on {}", wordlist.display(), units.len(), options.listen);
+
+    // Parsed once to check the cracks workers claim
+    let parsed: Arc<Vec<Box<dyn Hash>>> = Arc::new(hashes.iter().map(|line| hashes::parse(line)).collect::<Result<_, _>>()?);
+    let secret = Arc::new(options.secret);
+    let state = Arc::new(Mutex::new(State::new(wordlist, hashes, units, options.timeout)));
+
+    let listener = TcpListener::bind(&options.listen)?;
+    listener.set_nonblocking(true)?;
+
+    loop {
+        {
+            let mut state = state.lock().unwrap();
+            if state.finished() {
+                break;
+            }
+            state.expire();
+        }
+
+        match listener.accept() {
+            Ok((stream, address)) => {
+                stream.set_nonblocking(false)?;
+                let (state, parsed, secret) = (state.clone(), parsed.clone(), secret.clone());
+                thread::spawn(move || {
+                    if let Err(err) = handle(stream, &address.to_string(), &secret, &parsed, &state) {
+                        eprintln!("{}: {}", address, err);
+                    }
+                    state.lock().unwrap().release(&address.to_string());
+                });
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL),
+            Err(err) => return Err(err.into()),
+        }
+    }
+
+    let state = state.lock().unwrap();
+    Ok(state
+        .cracks
+        .iter()
+        .enumerate()
+        .filter_map(|(index, password)| {
+            password.as_ref().map(|password| Crack {
+                index,
+                password: password.clone(),
+            })
+        })
+        .collect())
+}
+
+fn handle(stream: TcpStream, address: &str, secret: &str, hashes: &[Box<dyn Hash>], state: &Mutex<State>) -> Result<(), Error> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+
+    // Nothing is sent to a worker before it proved it knows the secret
+    let name = match receive(&mut reader)? {
+        Some(Message::Hello { name, secret: claimed }) if same_secret(&claimed, secret) => format!("{} ({})", name, address),
+        Some(Message::Hello { .. }) => return Err(Error::Protocol(format!("wrong secret from {}", address))),
+        _ => return Err(Error::Protocol(format!("{} did not say hello", address))),
+    };
+    eprintln!("Worker {} joined", name);
+    {
+        let state = state.lock().unwrap();
+        let heartbeat_secs = (state.timeout.as_secs() / 3).max(1);
+        send(&mut writer, &Message::Hashes { hashes: state.hashes.clone(), heartbeat_secs })?;
+    }
+
+    while let Some(message) = receive(&mut reader)? {
+        match message {
+            Message::Request => {
+                let reply = next_unit(state, address)?;
+                let done = matches!(reply, Message::Done);
+                send(&mut writer, &reply)?;
+                if done {
+                    break;
+                }
+            }
+            Message::Progress { unit } => {
+                let mut state = state.lock().unwrap();
+                let timeout = state.timeout;
+                if let Some(assignment) = state.assigned.get_mut(&unit) {
+                    assignment.deadline = Instant::now() + timeout;
+                }
+            }
+            Message::Result { unit, candidates, cracks } => record(state, hashes, address, &name, unit, candidates, cracks)?,
+            _ => return Err(Error::Protocol(format!("unexpected message from {}", name))),
+        }
+    }
+    Ok(())
+}
+
+// Record the result of a unit assigned to this worker, keeping only the cracks that check out
+fn record(state: &Mutex<State>, hashes: &[Box<dyn Hash>], address: &str, name: &str, unit: usize, candidates: u64, cracks: Vec<Hit>) -> Result<(), Error> {
+    {
+        let state = state.lock().unwrap();
+        if unit >= state.units.len() {
+            return Err(Error::Protocol(format!("{} sent the result of unknown unit {}", name, unit)));
+        }
+        // A unit that timed out may have been handed to someone else since
+        if state.assigned.get(&unit).is_none_or(|assignment| assignment.worker != address) {
+            eprintln!("Ignoring the result of unit {} from {}, it is not assigned to it", unit, name);
+            return Ok(());
+        }
+    }
+
+    // Hashing can be slow, so it is done without holding the lock
+    let cracks: Vec<Hit> = cracks
+        .into_iter()
+        .filter(|Hit { index, password }| {
+            let verified = hashes.get(*index).is_some_and(|hash| hash.verify(&cracker::candidate(password)));
+            if !verified {
+                eprintln!("{} claimed a wrong password for hash {}, ignoring it", name, index);
+            }
+            verified
+        })
+        .collect();
+
+    let mut state = state.lock().unwrap();
+    for Hit { index, password } in cracks {
+        if state.cracks[index].is_none() {
+            eprintln!("{} cracked {}", name, state.hashes[index]);
+            state.cracks[index] = Some(password);
+        }
+    }
+    state.assigned.remove(&unit);
+    state.completed[unit] = true;
+    let completed = state.completed.iter().filter(|done| **done).count();
+    eprintln!(
+        "Unit {} done by {} ({} candidates), {}/{} units completed",
+        unit,
+        name,
+        candidates,
+        completed,
+        state.units.len()
+    );
+    Ok(())
+}
+
+// Compares every byte, so the time taken does not tell how much of the secret was right
+fn same_secret(claimed: &str, secret: &str) -> bool {
+    claimed.len() == secret.len() && claimed.bytes().zip(secret.bytes()).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+fn next_unit(state: &Mutex<State>, address: &str) -> Result<Message, Error> {
+    let mut state = state.lock().unwrap();
+    if state.finished() {
+        return Ok(Message::Done);
+    }
+
+    let unit = loop {
+        match state.pending.pop_front() {
+            // Skip units that were completed by a previous owner after being reassigned
+            Some(unit) if state.completed[unit] => continue,
+            Some(unit) => break unit,
+            // Everything is handed out, but a worker may still disappear
+            None => return Ok(Message::Wait { secs: WAIT_SECS }),
+        }
+    };
+
+    let deadline = Instant::now() + state.timeout;
+    state.assigned.insert(
+        unit,
+        Assignment {
+            worker: address.to_string(),
+            deadline,
+        },
+    );
+
+    let (start, end) = (state.units[unit].start, state.units[unit].end);
+    let mut data = vec![0u8; (end - start) as usize];
+    let mut file = File::open(&state.wordlist)?;
+    file.seek(SeekFrom::Start(start))?;
+    file.read_exact(&mut data)?;
+
+    let cracked = state
+        .cracks
+        .iter()
+        .enumerate()
+        .filter(|(_, password)| password.is_some())
+        .map(|(index, _)| index)
+        .collect();
+
+    Ok(Message::Unit {
+        unit,
+        start,
+        end,
+        cracked,
+        data: STANDARD.encode(&data),
+    })
+}
+
+// Cut the wordlist in ranges of about `unit_size` bytes, each ending after a newline
+fn split(wordlist: &Path, unit_size: u64) -> Result<Vec<Unit>, Error> {
+    let len = File::open(wordlist)?.metadata()?.len();
+    let mut reader = BufReader::new(File::open(wordlist)?);
+    let mut units = vec![];
+    let mut start = 0;
+
+    while start < len {
+        let target = (start + unit_size.max(1)).min(len);
+        reader.seek(SeekFrom::Start(target))?;
+        let mut rest = vec![];
+        let end = target + reader.read_until(b'\n', &mut rest)? as u64;
+        units.push(Unit { start, end });
+        start = end;
+    }
+    Ok(units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn split_on_line_boundaries() {
+        let path = std::env::temp_dir().join(format!("sha1_cracker_split_{}", std::process::id()));
+        let mut file = File::create(&path).unwrap();
+        file.write_all(b"password\n123456\nqwerty\nletmein\nno-newline").unwrap();
+
+        let units = split(&path, 10).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = units
+            .iter()
+            .flat_map(|unit| content[unit.start as usize..unit.end as usize].lines())
+            .collect();
+        std::fs::remove_file(&path).unwrap();
+
+        assert!(units.len() > 1);
+        assert_eq!(units.last().unwrap().end, content.len() as u64);
+        assert_eq!(lines, vec!["password", "123456", "qwerty", "letmein", "no-newline"]);
+    }
+
+    // SHA-1 of "password"
+    const HASH: &str = "5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8";
+
+    fn server(hash: &str) -> (Mutex<State>, Vec<Box<dyn Hash>>) {
+        let hashes = vec![hash.to_string()];
+        let parsed = hashes.iter().map(|line| hashes::parse(line).unwrap()).collect();
+        let units = vec![Unit { start: 0, end: 10 }, Unit { start: 10, end: 20 }];
+        let mut state = State::new(Path::new("wordlist"), hashes, units, Duration::from_secs(60));
+        let deadline = Instant::now() + state.timeout;
+        state.assigned.insert(0, Assignment { worker: "worker".to_string(), deadline });
+        (Mutex::new(state), parsed)
+    }
+
+    fn hit(password: &str) -> Vec<Hit> {
+        vec![Hit { index: 0, password: password.to_string() }]
+    }
+
+    #[test]
+    fn record_verifies_cracks() {
+        let (state, hashes) = server(HASH);
+        record(&state, &hashes, "worker", "worker", 0, 10, hit("wrong")).unwrap();
+        let state = state.into_inner().unwrap();
+        assert!(state.cracks[0].is_none());
+        assert!(state.completed[0]);
+
+        let (state, hashes) = server(HASH);
+        record(&state, &hashes, "worker", "worker", 0, 10, hit("password")).unwrap();
+        assert_eq!(state.into_inner().unwrap().cracks[0].as_deref(), Some("password"));
+
+        // Passwords that are not UTF-8 are reported as `$HEX[...]` and checked against their bytes
+        let (state, hashes) = server("d2f52bc4406898fc722c0b4e314f9b46fc85cde4");
+        record(&state, &hashes, "worker", "worker", 0, 10, hit("$HEX[636166e9]")).unwrap();
+        assert_eq!(state.into_inner().unwrap().cracks[0].as_deref(), Some("$HEX[636166e9]"));
+    }
+
+    #[test]
+    fn record_assigned_units_only() {
+        let (state, hashes) = server(HASH);
+        // Unit 1 is not assigned, unit 0 belongs to another worker, unit 2 does not exist
+        record(&state, &hashes, "worker", "worker", 1, 10, hit("password")).unwrap();
+        record(&state, &hashes, "other", "other", 0, 10, hit("password")).unwrap();
+        assert!(record(&state, &hashes, "worker", "worker", 2, 10, hit("password")).is_err());
+
+        let state = state.into_inner().unwrap();
+        assert!(state.cracks[0].is_none());
+        assert_eq!(state.completed, vec![false, false]);
+        assert!(state.assigned.contains_key(&0));
+    }
+
+    #[test]
+    fn secrets() {
+        assert!(same_secret("s3cret", "s3cret"));
+        assert!(!same_secret("s3cre", "s3cret"));
+        assert!(!same_secret("s3creT", "s3cret"));
+    }
+}
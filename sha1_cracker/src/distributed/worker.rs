@@ -0,0 +1,82 @@
+use crate::{
+    cracker::{self, Limits},
+    distributed::{receive, send, Hit, Message},
+    hashes,
+    Error,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::{
+    io::{BufReader, Cursor},
+    net::TcpStream,
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+// Pull units from the server until it says everything is done
+pub fn work(server: &str, name: &str, secret: &str) -> Result<(), Error> {
+    let stream = TcpStream::connect(server)?;
+    let writer = Arc::new(Mutex::new(stream.try_clone()?));
+    let mut reader = BufReader::new(stream);
+
+    let hello = Message::Hello { name: name.to_string(), secret: secret.to_string() };
+    send(&mut *writer.lock().unwrap(), &hello)?;
+    let (lines, heartbeat) = match receive(&mut reader)? {
+        Some(Message::Hashes { hashes, heartbeat_secs }) => (hashes, Duration::from_secs(heartbeat_secs)),
+        // The server hangs up on a wrong secret
+        None => return Err(Error::Protocol("the server closed the connection, wrong secret?".to_string())),
+        _ => return Err(Error::Protocol("expected the hash list".to_string())),
+    };
+    eprintln!("Connected to {}, {} hash(es) to crack", server, lines.len());
+
+    loop {
+        send(&mut *writer.lock().unwrap(), &Message::Request)?;
+        match receive(&mut reader)? {
+            Some(Message::Unit { unit, start, end, cracked, data }) => {
+                eprintln!("Cracking unit {} (bytes {}..{})", unit, start, end);
+                let data = STANDARD.decode(data).map_err(|err| Error::Protocol(format!("unit {}: {}", unit, err)))?;
+
+                // Only the hashes nobody cracked yet, `indexes` maps them back to the server list
+                let mut indexes = vec![];
+                let mut active = vec![];
+                for (index, line) in lines.iter().enumerate().filter(|(index, _)| !cracked.contains(index)) {
+                    indexes.push(index);
+                    active.push(hashes::parse(line)?);
+                }
+
+                // Dropping `stop` ends the heartbeats
+                let (stop, stopped) = mpsc::channel::<()>();
+                let heartbeat_thread = {
+                    let writer = writer.clone();
+                    thread::spawn(move || {
+                        while let Err(mpsc::RecvTimeoutError::Timeout) = stopped.recv_timeout(heartbeat) {
+                            let _ = send(&mut *writer.lock().unwrap(), &Message::Progress { unit });
+                        }
+                    })
+                };
+
+                let outcome = cracker::crack(Cursor::new(data), &active, &Limits::default());
+                drop(stop);
+                let _ = heartbeat_thread.join();
+                let outcome = outcome?;
+
+                let candidates = outcome.position;
+                let cracks = outcome
+                    .cracks
+                    .into_iter()
+                    .map(|crack| Hit {
+                        index: indexes[crack.index],
+                        password: crack.password,
+                    })
+                    .collect();
+                send(&mut *writer.lock().unwrap(), &Message::Result { unit, candidates, cracks })?;
+            }
+            Some(Message::Wait { secs }) => thread::sleep(Duration::from_secs(secs)),
+            Some(Message::Done) | None => break,
+            Some(_) => return Err(Error::Protocol("unexpected message from the server".to_string())),
+        }
+    }
+
+    eprintln!("Server has no more work, exiting");
+    Ok(())
+}
@@ -0,0 +1,25 @@
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("Io: {0}")]
+    Io(String),
+    #[error("Invalid hash: {0}")]
+    InvalidHash(String),
+    #[error("Invalid registry hive: {0}")]
+    InvalidHive(String),
+    #[error("Protocol: {0}")]
+    Protocol(String),
+}
+
+impl std::convert::From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err.to_string())
+    }
+}
+
+impl std::convert::From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::Protocol(err.to_string())
+    }
+}
@@ -0,0 +1,36 @@
+//! Offline extraction of password hashes from Windows registry hives.
+
+mod registry;
+mod sam;
+
+pub use sam::extract as extract_sam;
+
+const EMPTY_LM_HASH: &str = "aad3b435b51404eeaad3b435b51404ee";
+const EMPTY_NT_HASH: &str = "31d6cfe0d16ae931b73c59d7e0c089c0";
+
+/*
+An Account is a local user recovered from the SAM.
+    - `user`: The account name.
+    - `rid`: The relative identifier of the account (500 for Administrator).
+    - `lm_hash` / `nt_hash`: The decrypted hashes, None when the account has none stored.
+*/
+
+pub struct Account {
+    pub user: String,
+    pub rid: u32,
+    pub lm_hash: Option<[u8; 16]>,
+    pub nt_hash: Option<[u8; 16]>,
+}
+
+impl Account {
+    // pwdump format, which the NTLM hash parser reads back
+    pub fn pwdump(&self) -> String {
+        format!(
+            "{}:{}:{}:{}:::",
+            self.user,
+            self.rid,
+            self.lm_hash.map(hex::encode).unwrap_or_else(|| EMPTY_LM_HASH.to_string()),
+            self.nt_hash.map(hex::encode).unwrap_or_else(|| EMPTY_NT_HASH.to_string()),
+        )
+    }
+}
@@ -0,0 +1,349 @@
+//! Minimal read-only parser for offline registry hive files (regf).
+
+use crate::Error;
+use std::{fs, path::Path};
+
+const BASE_BLOCK_LEN: usize = 0x1000;
+const ROOT_CELL_OFFSET: usize = 0x24;
+
+// Offsets inside a key node
+const NK_SUBKEY_COUNT: usize = 0x14;
+const NK_SUBKEY_LIST: usize = 0x1c;
+const NK_VALUE_COUNT: usize = 0x24;
+const NK_VALUE_LIST: usize = 0x28;
+const NK_CLASS_NAME: usize = 0x30;
+const NK_NAME_LEN: usize = 0x48;
+const NK_CLASS_NAME_LEN: usize = 0x4a;
+const NK_NAME: usize = 0x4c;
+const NK_COMPRESSED_NAME: u16 = 0x0020;
+
+// Offsets inside a value key
+const VK_NAME_LEN: usize = 0x02;
+const VK_DATA_SIZE: usize = 0x04;
+const VK_DATA_OFFSET: usize = 0x08;
+const VK_FLAGS: usize = 0x10;
+const VK_NAME: usize = 0x14;
+const VK_COMPRESSED_NAME: u16 = 0x0001;
+const VK_DATA_INLINE: u32 = 0x8000_0000;
+
+// Values larger than this are split into segments behind a `db` cell
+const BIG_DATA_SEGMENT_LEN: usize = 16344;
+
+pub struct Hive {
+    data: Vec<u8>,
+}
+
+#[derive(Clone, Copy)]
+pub struct Key<'a> {
+    hive: &'a Hive,
+    cell: &'a [u8],
+}
+
+impl Hive {
+    pub fn open(path: &Path) -> Result<Self, Error> {
+        Self::from_bytes(fs::read(path)?)
+            .map_err(|_| Error::InvalidHive(format!("{}: not a registry hive", path.display())))
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self, Error> {
+        if data.len() < BASE_BLOCK_LEN || &data[..4] != b"regf" {
+            return Err(Error::InvalidHive("not a registry hive".to_string()));
+        }
+        Ok(Hive { data })
+    }
+
+    pub fn root(&self) -> Result<Key<'_>, Error> {
+        let offset = read_u32(&self.data, ROOT_CELL_OFFSET)? as usize;
+        Key::new(self, offset)
+    }
+
+    // Walk a backslash separated path from the root key, names are case insensitive
+    pub fn key(&self, path: &str) -> Result<Key<'_>, Error> {
+        let mut key = self.root()?;
+        for name in path.split('\\').filter(|name| !name.is_empty()) {
+            key = key.subkey(name)?;
+        }
+        Ok(key)
+    }
+
+    fn cell(&self, offset: usize) -> Result<&[u8], Error> {
+        let start = BASE_BLOCK_LEN + offset;
+        let size = read_u32(&self.data, start)? as i32;
+        // Allocated cells have a negative size
+        let size = size.unsigned_abs() as usize;
+        if size < 4 || start + size > self.data.len() {
+            return Err(Error::InvalidHive(format!("cell at {:#x} is out of bounds", offset)));
+        }
+        Ok(&self.data[start + 4..start + size])
+    }
+}
+
+impl<'a> Key<'a> {
+    fn new(hive: &'a Hive, offset: usize) -> Result<Self, Error> {
+        let cell = hive.cell(offset)?;
+        if cell.len() < NK_NAME || &cell[..2] != b"nk" {
+            return Err(Error::InvalidHive(format!("cell at {:#x} is not a key node", offset)));
+        }
+        Ok(Key { hive, cell })
+    }
+
+    pub fn name(&self) -> String {
+        let len = read_u16(self.cell, NK_NAME_LEN).unwrap_or(0) as usize;
+        let compressed = read_u16(self.cell, 0x02).unwrap_or(0) & NK_COMPRESSED_NAME != 0;
+        let name = self.cell.get(NK_NAME..NK_NAME + len).unwrap_or_default();
+        decode_name(name, compressed)
+    }
+
+    // Class names are where the SYSTEM hive hides the boot key
+    pub fn class_name(&self) -> Result<String, Error> {
+        let offset = read_u32(self.cell, NK_CLASS_NAME)? as usize;
+        let len = read_u16(self.cell, NK_CLASS_NAME_LEN)? as usize;
+        let cell = self.hive.cell(offset)?;
+        let data = cell
+            .get(..len)
+            .ok_or_else(|| Error::InvalidHive(format!("{}: class name is out of bounds", self.name())))?;
+        Ok(decode_name(data, false))
+    }
+
+    pub fn subkeys(&self) -> Result<Vec<Key<'a>>, Error> {
+        if read_u32(self.cell, NK_SUBKEY_COUNT)? == 0 {
+            return Ok(vec![]);
+        }
+        let mut offsets = vec![];
+        self.list_offsets(read_u32(self.cell, NK_SUBKEY_LIST)? as usize, &mut offsets)?;
+        offsets.into_iter().map(|offset| Key::new(self.hive, offset)).collect()
+    }
+
+    pub fn subkey(&self, name: &str) -> Result<Key<'a>, Error> {
+        self.subkeys()?
+            .into_iter()
+            .find(|key| key.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| Error::InvalidHive(format!("{}: no subkey named {}", self.name(), name)))
+    }
+
+    pub fn value(&self, name: &str) -> Result<Vec<u8>, Error> {
+        let count = read_u32(self.cell, NK_VALUE_COUNT)? as usize;
+        let not_found = || Error::InvalidHive(format!("{}: no value named {}", self.name(), name));
+        if count == 0 {
+            return Err(not_found());
+        }
+
+        let list = self.hive.cell(read_u32(self.cell, NK_VALUE_LIST)? as usize)?;
+        for index in 0..count {
+            let vk = self.hive.cell(read_u32(list, index * 4)? as usize)?;
+            if vk.len() < VK_NAME || &vk[..2] != b"vk" {
+                continue;
+            }
+            let name_len = read_u16(vk, VK_NAME_LEN)? as usize;
+            let compressed = read_u16(vk, VK_FLAGS)? & VK_COMPRESSED_NAME != 0;
+            let value_name = decode_name(vk.get(VK_NAME..VK_NAME + name_len).unwrap_or_default(), compressed);
+            if value_name.eq_ignore_ascii_case(name) {
+                return self.value_data(vk);
+            }
+        }
+        Err(not_found())
+    }
+
+    fn value_data(&self, vk: &[u8]) -> Result<Vec<u8>, Error> {
+        let size = read_u32(vk, VK_DATA_SIZE)?;
+        if size & VK_DATA_INLINE != 0 {
+            let size = (size & !VK_DATA_INLINE) as usize;
+            return Ok(vk[VK_DATA_OFFSET..VK_DATA_OFFSET + size.min(4)].to_vec());
+        }
+
+        let size = size as usize;
+        let cell = self.hive.cell(read_u32(vk, VK_DATA_OFFSET)? as usize)?;
+        if size > BIG_DATA_SEGMENT_LEN && cell.starts_with(b"db") {
+            let segments = read_u16(cell, 2)? as usize;
+            let list = self.hive.cell(read_u32(cell, 4)? as usize)?;
+            let mut data = vec![];
+            for index in 0..segments {
+                let segment = self.hive.cell(read_u32(list, index * 4)? as usize)?;
+                let remaining = size - data.len();
+                data.extend_from_slice(&segment[..remaining.min(BIG_DATA_SEGMENT_LEN).min(segment.len())]);
+            }
+            return Ok(data);
+        }
+
+        cell.get(..size)
+            .map(|data| data.to_vec())
+            .ok_or_else(|| Error::InvalidHive(format!("{}: value data is out of bounds", self.name())))
+    }
+
+    fn list_offsets(&self, offset: usize, offsets: &mut Vec<usize>) -> Result<(), Error> {
+        let list = self.hive.cell(offset)?;
+        let count = read_u16(list, 2)? as usize;
+        match &list[..2] {
+            // Fast leaf and hash leaf entries carry a name hint after each offset
+            b"lf" | b"lh" => {
+                for index in 0..count {
+                    offsets.push(read_u32(list, 4 + index * 8)? as usize);
+                }
+            }
+            b"li" => {
+                for index in 0..count {
+                    offsets.push(read_u32(list, 4 + index * 4)? as usize);
+                }
+            }
+            // Index roots point to other lists
+            b"ri" => {
+                for index in 0..count {
+                    self.list_offsets(read_u32(list, 4 + index * 4)? as usize, offsets)?;
+                }
+            }
+            _ => return Err(Error::InvalidHive(format!("{}: unknown subkey list", self.name()))),
+        }
+        Ok(())
+    }
+}
+
+fn decode_name(data: &[u8], compressed: bool) -> String {
+    if compressed {
+        data.iter().map(|byte| *byte as char).collect()
+    } else {
+        let units: Vec<u16> = data.chunks_exact(2).map(|unit| u16::from_le_bytes([unit[0], unit[1]])).collect();
+        String::from_utf16_lossy(&units)
+    }
+}
+
+pub fn read_u16(data: &[u8], offset: usize) -> Result<u16, Error> {
+    data.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| Error::InvalidHive(format!("read past the end of a cell at {:#x}", offset)))
+}
+
+pub fn read_u32(data: &[u8], offset: usize) -> Result<u32, Error> {
+    data.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or_else(|| Error::InvalidHive(format!("read past the end of a cell at {:#x}", offset)))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    /*
+    A TestKey is a key of a hive built in memory by `hive`.
+        - `class_name`: Stored as UTF-16, like the SYSTEM hive does.
+        - `values`: Values of 4 bytes or less are stored inline in the value key.
+    */
+
+    #[derive(Default)]
+    pub struct TestKey {
+        pub name: &'static str,
+        pub class_name: Option<&'static str>,
+        pub values: Vec<(&'static str, Vec<u8>)>,
+        pub subkeys: Vec<TestKey>,
+    }
+
+    impl TestKey {
+        pub fn new(name: &'static str, subkeys: Vec<TestKey>) -> Self {
+            TestKey { name, subkeys, ..Default::default() }
+        }
+    }
+
+    // A regf file with a single hive bin holding every cell
+    pub fn hive(root: &TestKey) -> Hive {
+        let mut bin = b"hbin".to_vec();
+        bin.resize(0x20, 0);
+        let root_offset = write_key(&mut bin, root);
+        let bin_len = bin.len() as u32;
+        bin[8..12].copy_from_slice(&bin_len.to_le_bytes());
+
+        let mut data = b"regf".to_vec();
+        data.resize(BASE_BLOCK_LEN, 0);
+        data[ROOT_CELL_OFFSET..ROOT_CELL_OFFSET + 4].copy_from_slice(&root_offset.to_le_bytes());
+        data.extend(bin);
+        Hive::from_bytes(data).unwrap()
+    }
+
+    fn write_cell(bin: &mut Vec<u8>, data: &[u8]) -> u32 {
+        let offset = bin.len() as u32;
+        let size = (data.len() + 4).next_multiple_of(8);
+        bin.extend((-(size as i32)).to_le_bytes());
+        bin.extend(data);
+        bin.resize(offset as usize + size, 0);
+        offset
+    }
+
+    fn write_key(bin: &mut Vec<u8>, key: &TestKey) -> u32 {
+        let subkeys: Vec<u32> = key.subkeys.iter().map(|subkey| write_key(bin, subkey)).collect();
+        let mut nk = vec![0u8; NK_NAME];
+        nk[..2].copy_from_slice(b"nk");
+        nk[2..4].copy_from_slice(&NK_COMPRESSED_NAME.to_le_bytes());
+
+        if !subkeys.is_empty() {
+            let mut list = b"lf".to_vec();
+            list.extend((subkeys.len() as u16).to_le_bytes());
+            for offset in &subkeys {
+                list.extend(offset.to_le_bytes());
+                list.extend([0; 4]);
+            }
+            nk[NK_SUBKEY_COUNT..NK_SUBKEY_COUNT + 4].copy_from_slice(&(subkeys.len() as u32).to_le_bytes());
+            nk[NK_SUBKEY_LIST..NK_SUBKEY_LIST + 4].copy_from_slice(&write_cell(bin, &list).to_le_bytes());
+        }
+
+        if !key.values.is_empty() {
+            let mut list = vec![];
+            for (name, data) in &key.values {
+                let mut vk = vec![0u8; VK_NAME];
+                vk[..2].copy_from_slice(b"vk");
+                vk[VK_NAME_LEN..VK_NAME_LEN + 2].copy_from_slice(&(name.len() as u16).to_le_bytes());
+                if data.len() <= 4 {
+                    let size = data.len() as u32 | VK_DATA_INLINE;
+                    vk[VK_DATA_SIZE..VK_DATA_SIZE + 4].copy_from_slice(&size.to_le_bytes());
+                    vk[VK_DATA_OFFSET..VK_DATA_OFFSET + data.len()].copy_from_slice(data);
+                } else {
+                    vk[VK_DATA_SIZE..VK_DATA_SIZE + 4].copy_from_slice(&(data.len() as u32).to_le_bytes());
+                    vk[VK_DATA_OFFSET..VK_DATA_OFFSET + 4].copy_from_slice(&write_cell(bin, data).to_le_bytes());
+                }
+                vk[VK_FLAGS..VK_FLAGS + 2].copy_from_slice(&VK_COMPRESSED_NAME.to_le_bytes());
+                vk.extend(name.as_bytes());
+                list.extend(write_cell(bin, &vk).to_le_bytes());
+            }
+            nk[NK_VALUE_COUNT..NK_VALUE_COUNT + 4].copy_from_slice(&(key.values.len() as u32).to_le_bytes());
+            nk[NK_VALUE_LIST..NK_VALUE_LIST + 4].copy_from_slice(&write_cell(bin, &list).to_le_bytes());
+        }
+
+        if let Some(class_name) = key.class_name {
+            let utf16: Vec<u8> = class_name.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+            nk[NK_CLASS_NAME..NK_CLASS_NAME + 4].copy_from_slice(&write_cell(bin, &utf16).to_le_bytes());
+            nk[NK_CLASS_NAME_LEN..NK_CLASS_NAME_LEN + 2].copy_from_slice(&(utf16.len() as u16).to_le_bytes());
+        }
+
+        nk[NK_NAME_LEN..NK_NAME_LEN + 2].copy_from_slice(&(key.name.len() as u16).to_le_bytes());
+        nk.extend(key.name.as_bytes());
+        write_cell(bin, &nk)
+    }
+
+    #[test]
+    fn keys_and_values() {
+        let hive = hive(&TestKey::new(
+            "ROOT",
+            vec![TestKey::new(
+                "Control",
+                vec![TestKey {
+                    name: "Lsa",
+                    class_name: Some("4a1b2c3d"),
+                    values: vec![("Current", 1u32.to_le_bytes().to_vec()), ("F", (0..64).collect())],
+                    ..Default::default()
+                }],
+            )],
+        ));
+
+        let lsa = hive.key("control\\LSA").unwrap();
+        assert_eq!(lsa.name(), "Lsa");
+        assert_eq!(lsa.class_name().unwrap(), "4a1b2c3d");
+        assert_eq!(read_u32(&lsa.value("current").unwrap(), 0).unwrap(), 1);
+        assert_eq!(lsa.value("F").unwrap(), (0..64).collect::<Vec<u8>>());
+        assert!(lsa.value("V").is_err());
+        assert!(lsa.subkeys().unwrap().is_empty());
+        assert!(hive.key("Control\\Nope").is_err());
+    }
+
+    #[test]
+    fn not_a_hive() {
+        assert!(Hive::from_bytes(vec![0; BASE_BLOCK_LEN]).is_err());
+        assert!(Hive::from_bytes(b"regf".to_vec()).is_err());
+    }
+}
@@ -0,0 +1,278 @@
+//! Local account hashes from offline SAM and SYSTEM hives.
+
+use crate::{
+    extract::{
+        registry::{read_u32, Hive},
+        Account,
+    },
+    hashes::{des_key, rc4},
+    Error,
+};
+use aes::{
+    cipher::{generic_array::GenericArray, BlockDecrypt, KeyInit},
+    Aes128,
+};
+use des::Des;
+use md5::{Digest, Md5};
+use std::path::Path;
+
+const BOOT_KEY_PERMUTATION: [usize; 16] = [8, 5, 4, 2, 11, 9, 13, 3, 0, 6, 1, 12, 14, 10, 15, 7];
+
+const QWERTY: &[u8] = b"!@#$%^&*()qwertyUIOPAzxcvbnmQQQQQQQQQQQQ)(*@&%\0";
+const DIGITS: &[u8] = b"0123456789012345678901234567890123456789\0";
+const NTPASSWORD: &[u8] = b"NTPASSWORD\0";
+const LMPASSWORD: &[u8] = b"LMPASSWORD\0";
+
+// Offset of the key data in the domain account F value
+const F_KEY_DATA: usize = 0x68;
+
+// Offsets of the (offset, length) pairs in the user V value, data starts after the header
+const V_USERNAME: usize = 0x0c;
+const V_LM_HASH: usize = 0x9c;
+const V_NT_HASH: usize = 0xa8;
+const V_DATA: usize = 0xcc;
+
+pub fn extract(sam: &Path, system: &Path) -> Result<Vec<Account>, Error> {
+    let boot_key = boot_key(&Hive::open(system)?)?;
+    accounts(&Hive::open(sam)?, &boot_key)
+}
+
+fn accounts(sam: &Hive, boot_key: &[u8; 16]) -> Result<Vec<Account>, Error> {
+    let account = sam.key("SAM\\Domains\\Account")?;
+    let hashed_boot_key = hashed_boot_key(&account.value("F")?, boot_key)?;
+
+    let mut accounts = vec![];
+    for user in account.subkey("Users")?.subkeys()? {
+        let Ok(rid) = u32::from_str_radix(&user.name(), 16) else {
+            // Skips the `Names` key
+            continue;
+        };
+        let v = user.value("V")?;
+
+        let name = utf16(field(&v, V_USERNAME)?);
+        let lm_hash = decrypt_hash(field(&v, V_LM_HASH)?, &hashed_boot_key, rid, LMPASSWORD)?;
+        let nt_hash = decrypt_hash(field(&v, V_NT_HASH)?, &hashed_boot_key, rid, NTPASSWORD)?;
+
+        accounts.push(Account {
+            user: name,
+            rid,
+            lm_hash,
+            nt_hash,
+        });
+    }
+
+    accounts.sort_by_key(|account| account.rid);
+    Ok(accounts)
+}
+
+pub fn boot_key(system: &Hive) -> Result<[u8; 16], Error> {
+    let current = read_u32(&system.key("Select")?.value("Current")?, 0)?;
+    let lsa = system.key(&format!("ControlSet{:03}\\Control\\Lsa", current))?;
+
+    let mut scrambled = String::new();
+    for name in ["JD", "Skew1", "GBG", "Data"] {
+        scrambled.push_str(&lsa.subkey(name)?.class_name()?);
+    }
+    let scrambled = hex::decode(scrambled.trim_end_matches('\0'))
+        .map_err(|_| Error::InvalidHive("boot key class names are not hex".to_string()))?;
+    if scrambled.len() != 16 {
+        return Err(Error::InvalidHive("boot key is not 16 bytes".to_string()));
+    }
+
+    let mut boot_key = [0u8; 16];
+    for (i, index) in BOOT_KEY_PERMUTATION.iter().enumerate() {
+        boot_key[i] = scrambled[*index];
+    }
+    Ok(boot_key)
+}
+
+fn hashed_boot_key(f: &[u8], boot_key: &[u8; 16]) -> Result<Vec<u8>, Error> {
+    let key_data = f
+        .get(F_KEY_DATA..)
+        .ok_or_else(|| Error::InvalidHive("domain account F value is too short".to_string()))?;
+    let invalid = || Error::InvalidHive("domain account key data is too short".to_string());
+
+    match key_data.first() {
+        // Revision(4) Length(4) Salt(16) Key(16) Checksum(16)
+        Some(1) => {
+            let salt = key_data.get(8..24).ok_or_else(invalid)?;
+            let encrypted = key_data.get(24..56).ok_or_else(invalid)?;
+            let rc4_key = Md5::new()
+                .chain_update(salt)
+                .chain_update(QWERTY)
+                .chain_update(boot_key)
+                .chain_update(DIGITS)
+                .finalize();
+            let hashed_boot_key = rc4(&rc4_key, encrypted);
+
+            let checksum = Md5::new()
+                .chain_update(&hashed_boot_key[..16])
+                .chain_update(DIGITS)
+                .chain_update(&hashed_boot_key[..16])
+                .chain_update(QWERTY)
+                .finalize();
+            if checksum.as_slice() != &hashed_boot_key[16..] {
+                return Err(Error::InvalidHive("hashed boot key checksum mismatch, wrong SYSTEM hive?".to_string()));
+            }
+            Ok(hashed_boot_key)
+        }
+        // Revision(4) Length(4) ChecksumLength(4) DataLength(4) Salt(16) Data
+        Some(2) => {
+            let data_len = read_u32(key_data, 12)? as usize;
+            let salt = key_data.get(16..32).ok_or_else(invalid)?;
+            let data = key_data.get(32..32 + data_len).ok_or_else(invalid)?;
+            let mut hashed_boot_key = aes_decrypt(boot_key, data, salt);
+
+            // The key is padded to two blocks (PKCS#7), a wrong boot key leaves garbage in the padding
+            if hashed_boot_key.len() != 32 || hashed_boot_key[16..].iter().any(|byte| *byte != 16) {
+                return Err(Error::InvalidHive("hashed boot key checksum mismatch, wrong SYSTEM hive?".to_string()));
+            }
+            hashed_boot_key.truncate(16);
+            Ok(hashed_boot_key)
+        }
+        _ => Err(Error::InvalidHive("unknown domain account key revision".to_string())),
+    }
+}
+
+fn decrypt_hash(data: &[u8], hashed_boot_key: &[u8], rid: u32, constant: &[u8]) -> Result<Option<[u8; 16]>, Error> {
+    let encrypted = match data.get(2) {
+        // PekId(2) Revision(2) Hash(16)
+        Some(1) if data.len() >= 20 => {
+            let rc4_key = Md5::new()
+                .chain_update(&hashed_boot_key[..16])
+                .chain_update(rid.to_le_bytes())
+                .chain_update(constant)
+                .finalize();
+            rc4(&rc4_key, &data[4..20])
+        }
+        // PekId(2) Revision(2) DataOffset(4) Salt(16) Hash
+        Some(2) if data.len() > 24 => aes_decrypt(&hashed_boot_key[..16], &data[24..], &data[8..24]),
+        // No hash stored for this account
+        _ => return Ok(None),
+    };
+    if encrypted.len() < 16 {
+        return Ok(None);
+    }
+
+    let rid = rid.to_le_bytes();
+    let key1 = [rid[0], rid[1], rid[2], rid[3], rid[0], rid[1], rid[2]];
+    let key2 = [rid[3], rid[0], rid[1], rid[2], rid[3], rid[0], rid[1]];
+
+    let mut hash = [0u8; 16];
+    for (i, key) in [key1, key2].iter().enumerate() {
+        let cipher = Des::new_from_slice(&des_key(key)).expect("des key is 8 bytes");
+        let mut block = GenericArray::clone_from_slice(&encrypted[i * 8..i * 8 + 8]);
+        cipher.decrypt_block(&mut block);
+        hash[i * 8..i * 8 + 8].copy_from_slice(&block);
+    }
+    Ok(Some(hash))
+}
+
+// AES-128-CBC, each block is chained with the ciphertext of the previous one
+fn aes_decrypt(key: &[u8], data: &[u8], iv: &[u8]) -> Vec<u8> {
+    let cipher = Aes128::new_from_slice(key).expect("aes128 key is 16 bytes");
+    let mut previous: [u8; 16] = iv.try_into().expect("aes iv is 16 bytes");
+    let mut plaintext = vec![];
+    for chunk in data.chunks(16) {
+        let mut block = [0u8; 16];
+        block[..chunk.len()].copy_from_slice(chunk);
+        let ciphertext = block;
+        cipher.decrypt_block(GenericArray::from_mut_slice(&mut block));
+        plaintext.extend(block.iter().zip(previous.iter()).map(|(a, b)| a ^ b));
+        previous = ciphertext;
+    }
+    plaintext
+}
+
+// Read the (offset, length) pair at `entry` and return the data it points to
+fn field(v: &[u8], entry: usize) -> Result<&[u8], Error> {
+    let offset = read_u32(v, entry)? as usize + V_DATA;
+    let len = read_u32(v, entry + 4)? as usize;
+    v.get(offset..offset + len)
+        .ok_or_else(|| Error::InvalidHive("user V value is too short".to_string()))
+}
+
+fn utf16(data: &[u8]) -> String {
+    let units: Vec<u16> = data.chunks_exact(2).map(|unit| u16::from_le_bytes([unit[0], unit[1]])).collect();
+    String::from_utf16_lossy(&units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::registry::tests::{hive, TestKey};
+
+    // Class names of the Lsa keys and the boot key they hold
+    const CLASS_NAMES: [(&str, &str); 4] = [("JD", "4a1b2c3d"), ("Skew1", "5e6f7081"), ("GBG", "92a3b4c5"), ("Data", "d6e7f809")];
+    const BOOT_KEY: &str = "926f5e2cc5a3e73d4a701bd6f8b40981";
+
+    // The NT hash of `password`, for RID 500, encrypted with the hashed boot key 303132...3f
+    const AES_KEY_DATA: &str = "02000000480000001000000020000000a0a1a2a3a4a5a6a7a8a9aaabacadaeaffe4b1fbbe786b1f3573c1f7292038ac9c9987dc76cb32cd64c18d2dd8954d821";
+    const AES_NT_HASH: &str = "0000020010000000c0c1c2c3c4c5c6c7c8c9cacbcccdcecfb2d05e3a87f34b56955188fa1cfb2b7fd5de505d5615f0f1779d60a107d000e4";
+    const RC4_KEY_DATA: &str = "0100000028000000a0a1a2a3a4a5a6a7a8a9aaabacadaeaf907ce2a35fccc5eb9effb68cf024bb161ab06b5d987679f4d51eb08d8ad353bb";
+    const RC4_NT_HASH: &str = "00000100f5e3c2f19accfa400c6c78b7dca8a756";
+    const NT_HASH: &str = "8846f7eaee8fb117ad06bdd830b7586c";
+
+    fn system() -> Hive {
+        let lsa = CLASS_NAMES
+            .iter()
+            .map(|(name, class_name)| TestKey { name, class_name: Some(class_name), ..Default::default() })
+            .collect();
+        hive(&TestKey::new(
+            "ROOT",
+            vec![
+                TestKey { name: "Select", values: vec![("Current", 1u32.to_le_bytes().to_vec())], ..Default::default() },
+                TestKey::new("ControlSet001", vec![TestKey::new("Control", vec![TestKey::new("Lsa", lsa)])]),
+            ],
+        ))
+    }
+
+    fn sam(key_data: &str, nt_hash: &str) -> Hive {
+        let mut f = vec![0u8; F_KEY_DATA];
+        f.extend(hex::decode(key_data).unwrap());
+
+        // Offsets in the V value are relative to the end of its header
+        let name: Vec<u8> = "Administrator".encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect();
+        let nt_hash = hex::decode(nt_hash).unwrap();
+        let mut v = vec![0u8; V_DATA];
+        v[V_USERNAME + 4..V_USERNAME + 8].copy_from_slice(&(name.len() as u32).to_le_bytes());
+        v[V_NT_HASH..V_NT_HASH + 4].copy_from_slice(&(name.len() as u32).to_le_bytes());
+        v[V_NT_HASH + 4..V_NT_HASH + 8].copy_from_slice(&(nt_hash.len() as u32).to_le_bytes());
+        v.extend(name);
+        v.extend(nt_hash);
+
+        let users = vec![
+            TestKey { name: "000001F4", values: vec![("V", v)], ..Default::default() },
+            TestKey::new("Names", vec![TestKey::new("Administrator", vec![])]),
+        ];
+        let account = TestKey { name: "Account", values: vec![("F", f)], subkeys: vec![TestKey::new("Users", users)], ..Default::default() };
+        hive(&TestKey::new("ROOT", vec![TestKey::new("SAM", vec![TestKey::new("Domains", vec![account])])]))
+    }
+
+    #[test]
+    fn boot_key_from_system() {
+        assert_eq!(hex::encode(boot_key(&system()).unwrap()), BOOT_KEY);
+    }
+
+    #[test]
+    fn aes() {
+        let boot_key = boot_key(&system()).unwrap();
+        let accounts = accounts(&sam(AES_KEY_DATA, AES_NT_HASH), &boot_key).unwrap();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].pwdump(), format!("Administrator:500:aad3b435b51404eeaad3b435b51404ee:{}:::", NT_HASH));
+    }
+
+    #[test]
+    fn rc4() {
+        let boot_key = boot_key(&system()).unwrap();
+        let accounts = accounts(&sam(RC4_KEY_DATA, RC4_NT_HASH), &boot_key).unwrap();
+        assert_eq!(accounts[0].nt_hash.map(hex::encode).as_deref(), Some(NT_HASH));
+    }
+
+    #[test]
+    fn wrong_boot_key() {
+        let boot_key = [0u8; 16];
+        assert!(accounts(&sam(AES_KEY_DATA, AES_NT_HASH), &boot_key).is_err());
+        assert!(accounts(&sam(RC4_KEY_DATA, RC4_NT_HASH), &boot_key).is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! Building blocks shared by the crypt(3) style formats.
+
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+const CRYPT_ALPHABET: &[u8] = b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+// Encode `bytes` 3 at a time following `order`, the last group may be shorter
+pub fn encode(bytes: &[u8], order: &[&[usize]]) -> String {
+    let mut encoded = String::new();
+    for group in order {
+        let mut value = 0u32;
+        for (i, index) in group.iter().enumerate() {
+            value |= (bytes[*index] as u32) << (8 * (group.len() - 1 - i));
+        }
+        for _ in 0..group.len() + 1 {
+            encoded.push(CRYPT_ALPHABET[(value & 0x3f) as usize] as char);
+            value >>= 6;
+        }
+    }
+    encoded
+}
+
+const MD5_ORDER: &[&[usize]] = &[&[0, 6, 12], &[1, 7, 13], &[2, 8, 14], &[3, 9, 15], &[4, 10, 5], &[11]];
+
+// MD5 based crypt from Poul-Henning Kamp, `magic` is `$1$` or `$apr1$` for Apache.
+// Returns only the encoded digest.
+pub fn md5_crypt(password: &[u8], salt: &[u8], magic: &[u8]) -> String {
+    let digest_b = Md5::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(password)
+        .finalize();
+
+    let mut ctx = Md5::new()
+        .chain_update(password)
+        .chain_update(magic)
+        .chain_update(salt);
+    for chunk in password.chunks(16) {
+        ctx.update(&digest_b[..chunk.len()]);
+    }
+    let mut length = password.len();
+    while length > 0 {
+        if length & 1 == 1 {
+            ctx.update([0]);
+        } else {
+            ctx.update(&password[..1]);
+        }
+        length >>= 1;
+    }
+    let mut digest = ctx.finalize();
+
+    for round in 0..1000 {
+        let mut ctx = Md5::new();
+        if round % 2 == 1 {
+            ctx.update(password);
+        } else {
+            ctx.update(digest);
+        }
+        if round % 3 != 0 {
+            ctx.update(salt);
+        }
+        if round % 7 != 0 {
+            ctx.update(password);
+        }
+        if round % 2 == 1 {
+            ctx.update(digest);
+        } else {
+            ctx.update(password);
+        }
+        digest = ctx.finalize();
+    }
+
+    encode(&digest, MD5_ORDER)
+}
+
+const SHA256_ORDER: &[&[usize]] = &[
+    &[0, 10, 20],
+    &[21, 1, 11],
+    &[12, 22, 2],
+    &[3, 13, 23],
+    &[24, 4, 14],
+    &[15, 25, 5],
+    &[6, 16, 26],
+    &[27, 7, 17],
+    &[18, 28, 8],
+    &[9, 19, 29],
+    &[31, 30],
+];
+
+// SHA-256 based crypt from Ulrich Drepper's specification, returns only the encoded digest.
+// Unlike `$5$` the salt is not truncated to 16 bytes since MySQL uses 20 byte salts.
+pub fn sha256_crypt(password: &[u8], salt: &[u8], rounds: u32) -> String {
+    let digest_b = Sha256::new()
+        .chain_update(password)
+        .chain_update(salt)
+        .chain_update(password)
+        .finalize();
+
+    let mut ctx = Sha256::new().chain_update(password).chain_update(salt);
+    for chunk in password.chunks(32) {
+        ctx.update(&digest_b[..chunk.len()]);
+    }
+    let mut length = password.len();
+    while length > 0 {
+        if length & 1 == 1 {
+            ctx.update(digest_b);
+        } else {
+            ctx.update(password);
+        }
+        length >>= 1;
+    }
+    let digest_a = ctx.finalize();
+
+    let mut ctx = Sha256::new();
+    for _ in 0..password.len() {
+        ctx.update(password);
+    }
+    let digest_p = ctx.finalize();
+    let p_sequence: Vec<u8> = digest_p.iter().cycle().take(password.len()).copied().collect();
+
+    let mut ctx = Sha256::new();
+    for _ in 0..16 + digest_a[0] as usize {
+        ctx.update(salt);
+    }
+    let digest_s = ctx.finalize();
+    let s_sequence: Vec<u8> = digest_s.iter().cycle().take(salt.len()).copied().collect();
+
+    let mut digest_c = digest_a;
+    for round in 0..rounds {
+        let mut ctx = Sha256::new();
+        if round % 2 == 1 {
+            ctx.update(&p_sequence);
+        } else {
+            ctx.update(digest_c);
+        }
+        if round % 3 != 0 {
+            ctx.update(&s_sequence);
+        }
+        if round % 7 != 0 {
+            ctx.update(&p_sequence);
+        }
+        if round % 2 == 1 {
+            ctx.update(digest_c);
+        } else {
+            ctx.update(&p_sequence);
+        }
+        digest_c = ctx.finalize();
+    }
+
+    encode(&digest_c, SHA256_ORDER)
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn md5_crypt() {
+        // Same output as `openssl passwd -1` and `openssl passwd -apr1`
+        assert_eq!(super::md5_crypt(b"password", b"saltsalt", b"$1$"), "qjXMvbEw8oaL.CzflDtaK/");
+        assert_eq!(super::md5_crypt(b"password", b"rNe5Bq2q", b"$apr1$"), "J0rLoeeyH0zAE2n/D0ZTL/");
+    }
+
+    #[test]
+    fn sha256_crypt() {
+        // Same output as `openssl passwd -5 -salt saltstring`
+        let digest = super::sha256_crypt(b"Hello world!", b"saltstring", 5000);
+        assert_eq!(digest, "5B8vYYiY.CVt1RlTTf8KbXBH3hsxY/GNooZaBBGWEc5");
+    }
+}
@@ -0,0 +1,124 @@
+//! crypt(3) style hashes found in Apache `.htpasswd` files and `/etc/shadow`.
+
+use crate::{
+    hashes::{crypt, Hash},
+    Error,
+};
+
+const APR1_PREFIX: &str = "$apr1$";
+const BCRYPT_LEN: usize = 60;
+const DES_CRYPT_LEN: usize = 13;
+
+enum Scheme {
+    Apr1 { salt: String, digest: String },
+    Unix { hash: String },
+}
+
+pub struct Htpasswd {
+    format: &'static str,
+    scheme: Scheme,
+}
+
+impl Htpasswd {
+    pub fn matches(hash: &str) -> bool {
+        ["$apr1$", "$1$", "$2a$", "$2b$", "$2y$", "$5$", "$6$"]
+            .iter()
+            .any(|prefix| hash.starts_with(prefix))
+            || is_des_crypt(hash)
+    }
+
+    pub fn parse(hash: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidHash(hash.to_string());
+
+        if let Some(rest) = hash.strip_prefix(APR1_PREFIX) {
+            let (salt, digest) = rest.split_once('$').ok_or_else(invalid)?;
+            if salt.is_empty() || salt.len() > 8 || digest.len() != 22 {
+                return Err(invalid());
+            }
+            return Ok(Htpasswd {
+                format: "apr1",
+                scheme: Scheme::Apr1 {
+                    salt: salt.to_string(),
+                    digest: digest.to_string(),
+                },
+            });
+        }
+
+        let format = if hash.starts_with("$1$") {
+            "md5crypt"
+        } else if hash.starts_with("$2") && hash.len() == BCRYPT_LEN {
+            "bcrypt"
+        } else if hash.starts_with("$5$") {
+            "sha256crypt"
+        } else if hash.starts_with("$6$") {
+            "sha512crypt"
+        } else if is_des_crypt(hash) {
+            "descrypt"
+        } else {
+            return Err(invalid());
+        };
+
+        Ok(Htpasswd {
+            format,
+            scheme: Scheme::Unix {
+                hash: hash.to_string(),
+            },
+        })
+    }
+}
+
+impl Hash for Htpasswd {
+    fn format(&self) -> &'static str {
+        self.format
+    }
+
+    fn label(&self) -> Option<String> {
+        None
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        match &self.scheme {
+            Scheme::Apr1 { salt, digest } => {
+                crypt::md5_crypt(candidate, salt.as_bytes(), APR1_PREFIX.as_bytes()) == *digest
+            }
+            Scheme::Unix { hash } => pwhash::unix::verify(candidate, hash),
+        }
+    }
+}
+
+fn is_des_crypt(hash: &str) -> bool {
+    hash.len() == DES_CRYPT_LEN
+        && hash
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'.' || byte == b'/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Htpasswd;
+    use crate::hashes::Hash;
+
+    #[test]
+    fn apr1() {
+        let hash = Htpasswd::parse("$apr1$rNe5Bq2q$J0rLoeeyH0zAE2n/D0ZTL/").unwrap();
+        assert_eq!(hash.format(), "apr1");
+        assert!(hash.verify(b"password"));
+        assert!(!hash.verify(b"Password"));
+    }
+
+    #[test]
+    fn bcrypt() {
+        let hash = Htpasswd::parse("$2a$05$CCCCCCCCCCCCCCCCCCCCC.E5YPO9kmyuRGyh0XouQYb4YMJKvyOeW").unwrap();
+        assert_eq!(hash.format(), "bcrypt");
+        assert!(hash.verify(b"U*U"));
+        assert!(!hash.verify(b"U*V"));
+    }
+
+    #[test]
+    fn des_crypt() {
+        let hash = Htpasswd::parse("abJnggxhB/yWI").unwrap();
+        assert_eq!(hash.format(), "descrypt");
+        assert!(hash.verify(b"password"));
+        assert!(!hash.verify(b"Password"));
+    }
+}
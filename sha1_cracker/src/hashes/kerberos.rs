@@ -0,0 +1,372 @@
+//! Kerberoast (`$krb5tgs$`) and AS-REP roast (`$krb5asrep$`) hashes as produced by impacket and Rubeus.
+
+use crate::{
+    hashes::{netntlm::nt_hash, Hash},
+    Error,
+};
+use aes::{
+    cipher::{generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit},
+    Aes128, Aes256,
+};
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use sha1::Sha1;
+
+pub const TGS_PREFIX: &str = "$krb5tgs$";
+pub const ASREP_PREFIX: &str = "$krb5asrep$";
+
+const PBKDF2_ROUNDS: u32 = 4096;
+const AES_BLOCK_LEN: usize = 16;
+const AES_CHECKSUM_LEN: usize = 12;
+
+// Key usage numbers from RFC 4120
+const USAGE_TGS_REP_TICKET: u32 = 2;
+const USAGE_AS_REP_ENC_PART: u32 = 8;
+
+enum Cipher {
+    Rc4Hmac {
+        usage: u32,
+    },
+    Aes {
+        key_len: usize,
+        // Realm in uppercase followed by the user name
+        salt: Vec<u8>,
+    },
+}
+
+pub struct Kerberos {
+    format: &'static str,
+    label: Option<String>,
+    cipher: Cipher,
+    checksum: Vec<u8>,
+    edata: Vec<u8>,
+}
+
+impl Kerberos {
+    pub fn matches(line: &str) -> bool {
+        line.starts_with(TGS_PREFIX) || line.starts_with(ASREP_PREFIX)
+    }
+
+    pub fn parse(line: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidHash(line.to_string());
+
+        let (format, cipher, label, data) = if let Some(rest) = line.strip_prefix("$krb5tgs$23$") {
+            // `*user$realm$spn*$checksum$edata2` or `checksum$edata2`
+            let (label, data) = match rest.strip_prefix('*') {
+                Some(rest) => {
+                    let (account, data) = rest.split_once("*$").ok_or_else(invalid)?;
+                    let mut parts = account.splitn(3, '$');
+                    let label = match (parts.next(), parts.next()) {
+                        (Some(user), Some(realm)) => format!("{}@{}", user, realm),
+                        _ => account.to_string(),
+                    };
+                    (Some(label), data)
+                }
+                None => (None, rest),
+            };
+            let cipher = Cipher::Rc4Hmac {
+                usage: USAGE_TGS_REP_TICKET,
+            };
+            ("krb5tgs-rc4", cipher, label, data)
+        } else if let Some(rest) = line.strip_prefix("$krb5asrep$23$") {
+            // `user@realm:checksum$edata2` or `checksum$edata2`
+            let (label, data) = match rest.rsplit_once(':') {
+                Some((account, data)) => (Some(account.to_string()), data),
+                None => (None, rest),
+            };
+            let cipher = Cipher::Rc4Hmac {
+                usage: USAGE_AS_REP_ENC_PART,
+            };
+            ("krb5asrep-rc4", cipher, label, data)
+        } else if let Some(rest) = line
+            .strip_prefix("$krb5tgs$17$")
+            .or_else(|| line.strip_prefix("$krb5tgs$18$"))
+        {
+            // `user$realm$*spn*$checksum$edata2` or `user$realm$checksum$edata2`
+            let (format, key_len) = if line.starts_with("$krb5tgs$17$") {
+                ("krb5tgs-aes128", 16)
+            } else {
+                ("krb5tgs-aes256", 32)
+            };
+            let (user, rest) = rest.split_once('$').ok_or_else(invalid)?;
+            let (realm, rest) = rest.split_once('$').ok_or_else(invalid)?;
+            let data = match rest.strip_prefix('*') {
+                Some(rest) => rest.split_once("*$").ok_or_else(invalid)?.1,
+                None => rest,
+            };
+            let cipher = Cipher::Aes {
+                key_len,
+                salt: format!("{}{}", realm.to_uppercase(), user).into_bytes(),
+            };
+            (format, cipher, Some(format!("{}@{}", user, realm)), data)
+        } else {
+            return Err(invalid());
+        };
+
+        let (checksum, edata) = data.split_once('$').ok_or_else(invalid)?;
+        let checksum = hex::decode(checksum).map_err(|_| invalid())?;
+        let edata = hex::decode(edata).map_err(|_| invalid())?;
+
+        let checksum_len = match cipher {
+            Cipher::Rc4Hmac { .. } => 16,
+            Cipher::Aes { .. } => AES_CHECKSUM_LEN,
+        };
+        if checksum.len() != checksum_len || edata.len() < AES_BLOCK_LEN {
+            return Err(invalid());
+        }
+
+        Ok(Kerberos {
+            format,
+            label,
+            cipher,
+            checksum,
+            edata,
+        })
+    }
+}
+
+impl Hash for Kerberos {
+    fn format(&self) -> &'static str {
+        self.format
+    }
+
+    fn label(&self) -> Option<String> {
+        self.label.clone()
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        match &self.cipher {
+            Cipher::Rc4Hmac { usage } => {
+                let k1 = hmac_md5(&nt_hash(candidate), &usage.to_le_bytes());
+                let k3 = hmac_md5(&k1, &self.checksum);
+                let plaintext = rc4(&k3, &self.edata);
+                hmac_md5(&k1, &plaintext) == self.checksum.as_slice()
+            }
+            Cipher::Aes { key_len, salt } => {
+                let key = string_to_key(candidate, salt, *key_len, PBKDF2_ROUNDS);
+                let usage = USAGE_TGS_REP_TICKET.to_be_bytes();
+                let ke = derive_key(&key, &[&usage[..], &[0xaa]].concat());
+                let ki = derive_key(&key, &[&usage[..], &[0x55]].concat());
+
+                let plaintext = cts_decrypt(&AesKey::new(&ke), &self.edata);
+                let mut mac = <Hmac<Sha1> as Mac>::new_from_slice(&ki).expect("hmac accepts any key length");
+                mac.update(&plaintext);
+                mac.finalize().into_bytes()[..AES_CHECKSUM_LEN] == self.checksum[..]
+            }
+        }
+    }
+}
+
+fn hmac_md5(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = <Hmac<Md5> as Mac>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+pub fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut state: Vec<u8> = (0..=255).collect();
+    let mut j: u8 = 0;
+    for i in 0..256 {
+        j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+        state.swap(i, j as usize);
+    }
+
+    let (mut i, mut j) = (0u8, 0u8);
+    data.iter()
+        .map(|byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(state[i as usize]);
+            state.swap(i as usize, j as usize);
+            byte ^ state[state[i as usize].wrapping_add(state[j as usize]) as usize]
+        })
+        .collect()
+}
+
+enum AesKey {
+    Aes128(Box<Aes128>),
+    Aes256(Box<Aes256>),
+}
+
+impl AesKey {
+    fn new(key: &[u8]) -> Self {
+        match key.len() {
+            16 => AesKey::Aes128(Box::new(Aes128::new_from_slice(key).expect("aes128 key is 16 bytes"))),
+            _ => AesKey::Aes256(Box::new(Aes256::new_from_slice(key).expect("aes256 key is 32 bytes"))),
+        }
+    }
+
+    fn encrypt(&self, block: &mut [u8]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            AesKey::Aes128(cipher) => cipher.encrypt_block(block),
+            AesKey::Aes256(cipher) => cipher.encrypt_block(block),
+        }
+    }
+
+    fn decrypt(&self, block: &mut [u8]) {
+        let block = GenericArray::from_mut_slice(block);
+        match self {
+            AesKey::Aes128(cipher) => cipher.decrypt_block(block),
+            AesKey::Aes256(cipher) => cipher.decrypt_block(block),
+        }
+    }
+}
+
+// RFC 3962 string-to-key: PBKDF2-HMAC-SHA1 followed by DK(tkey, "kerberos")
+fn string_to_key(password: &[u8], salt: &[u8], key_len: usize, rounds: u32) -> Vec<u8> {
+    let mut tkey = vec![0u8; key_len];
+    pbkdf2::pbkdf2_hmac::<Sha1>(password, salt, rounds, &mut tkey);
+    derive_key(&tkey, b"kerberos")
+}
+
+// RFC 3961 DK: encrypt the n-folded constant repeatedly until enough key material is produced
+fn derive_key(key: &[u8], constant: &[u8]) -> Vec<u8> {
+    let cipher = AesKey::new(key);
+    let mut block = nfold(constant, AES_BLOCK_LEN);
+    let mut derived = Vec::with_capacity(key.len());
+    while derived.len() < key.len() {
+        cipher.encrypt(&mut block);
+        derived.extend_from_slice(&block);
+    }
+    derived.truncate(key.len());
+    derived
+}
+
+// RFC 3961 n-fold: concatenate copies of the input rotated by 13 bits each time up to the
+// least common multiple of both lengths, then add the chunks with one's complement addition
+fn nfold(input: &[u8], out_len: usize) -> Vec<u8> {
+    let in_len = input.len();
+    let lcm = in_len * out_len / gcd(in_len, out_len);
+    let in_bits = in_len * 8;
+
+    let mut buffer = Vec::with_capacity(lcm);
+    for copy in 0..lcm / in_len {
+        let rotation = (13 * copy) % in_bits;
+        for byte in 0..in_len {
+            let mut value = 0u8;
+            for bit in 0..8 {
+                let source = (byte * 8 + bit + in_bits - rotation) % in_bits;
+                let source_bit = (input[source / 8] >> (7 - source % 8)) & 1;
+                value |= source_bit << (7 - bit);
+            }
+            buffer.push(value);
+        }
+    }
+
+    let mut out = vec![0u8; out_len];
+    for chunk in buffer.chunks(out_len) {
+        let mut carry = 0u16;
+        for i in (0..out_len).rev() {
+            let sum = out[i] as u16 + chunk[i] as u16 + carry;
+            out[i] = sum as u8;
+            carry = sum >> 8;
+        }
+        let mut i = out_len - 1;
+        while carry != 0 {
+            let sum = out[i] as u16 + carry;
+            out[i] = sum as u8;
+            carry = sum >> 8;
+            i = (i + out_len - 1) % out_len;
+        }
+    }
+    out
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+// AES-CBC with ciphertext stealing and a zero IV, as used by the Kerberos AES enctypes
+fn cts_decrypt(cipher: &AesKey, ciphertext: &[u8]) -> Vec<u8> {
+    let mut plaintext = Vec::with_capacity(ciphertext.len());
+    let blocks = ciphertext.len().div_ceil(AES_BLOCK_LEN);
+    let mut previous = [0u8; AES_BLOCK_LEN];
+
+    if blocks == 1 {
+        let mut block = ciphertext.to_vec();
+        cipher.decrypt(&mut block);
+        return block;
+    }
+
+    // Regular CBC up to the last two blocks
+    for chunk in ciphertext.chunks(AES_BLOCK_LEN).take(blocks - 2) {
+        let mut block = chunk.to_vec();
+        cipher.decrypt(&mut block);
+        plaintext.extend(block.iter().zip(previous.iter()).map(|(a, b)| a ^ b));
+        previous.copy_from_slice(chunk);
+    }
+
+    // The last two blocks are swapped and the final one is truncated
+    let tail = &ciphertext[(blocks - 2) * AES_BLOCK_LEN..];
+    let (stolen, last) = tail.split_at(AES_BLOCK_LEN);
+
+    let mut decrypted = stolen.to_vec();
+    cipher.decrypt(&mut decrypted);
+    let last_plain: Vec<u8> = last.iter().zip(decrypted.iter()).map(|(a, b)| a ^ b).collect();
+
+    let mut full = last.to_vec();
+    full.extend_from_slice(&decrypted[last.len()..]);
+    cipher.decrypt(&mut full);
+    plaintext.extend(full.iter().zip(previous.iter()).map(|(a, b)| a ^ b));
+    plaintext.extend(last_plain);
+
+    plaintext
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Kerberos;
+    use crate::hashes::Hash;
+
+    #[test]
+    fn nfold() {
+        assert_eq!(hex::encode(super::nfold(b"012345", 8)), "be072631276b1955");
+        assert_eq!(hex::encode(super::nfold(b"password", 7)), "78a07b6caf85fa");
+        assert_eq!(hex::encode(super::nfold(b"kerberos", 16)), "6b65726265726f737b9b5b2b93132b93");
+    }
+
+    #[test]
+    fn string_to_key() {
+        // RFC 3962 appendix B
+        let key = super::string_to_key(b"password", b"ATHENA.MIT.EDUraeburn", 16, 1200);
+        assert_eq!(hex::encode(key), "4c01cd46d632d01e6dbe230a01ed642a");
+        let key = super::string_to_key(b"password", b"ATHENA.MIT.EDUraeburn", 32, 1200);
+        assert_eq!(hex::encode(key), "55a6ac740ad17b4846941051e1e8b0a7548d93b0ab30a8bc3ff16280382b8c2a");
+    }
+
+    #[test]
+    fn tgs_rc4() {
+        let hash = Kerberos::parse("$krb5tgs$23$*svc_web$BLACKHAT.LAB$HTTP/web.blackhat.lab*$a5fbbf2aecfd55b905b61011c126d978$a916fe83ef033372191aa3b67077df2474fa86606c1ce357cff604a33221360bf61a608d6b0830dc403b98eb3d3b2f9e6126a9699dbc75b6764c7ccd").unwrap();
+        assert_eq!(hash.label().unwrap(), "svc_web@BLACKHAT.LAB");
+        assert!(hash.verify(b"password"));
+        assert!(!hash.verify(b"Password"));
+    }
+
+    #[test]
+    fn asrep_rc4() {
+        let hash = Kerberos::parse("$krb5asrep$23$jdoe@BLACKHAT.LAB:f7f1d07b95c29b69163df25fa00958c1$7903912beb34afdb90e87d68c342ad36f9f86f7232b3e8cc14d150d324acbd89f388c824285d86f68cc635bf3df864759dfa647a46e3a3cadd2fbee7").unwrap();
+        assert_eq!(hash.label().unwrap(), "jdoe@BLACKHAT.LAB");
+        assert!(hash.verify(b"password"));
+        assert!(!hash.verify(b"Password"));
+    }
+
+    #[test]
+    fn tgs_aes128() {
+        let hash = Kerberos::parse("$krb5tgs$17$svc_sql$BLACKHAT.LAB$*MSSQLSvc/db.blackhat.lab*$5bf84f6643705f6e6c7284bc$9aa8bc785aae14fd6d338ad8f230475983bb0380dabe8cd4e559ece7417f759a0f97d3180aee8846deca5bcc7b904d3aca113754a99a0176e8396b85b02c8976bc477980ceb9a631dc80b30867e0e119024ef0").unwrap();
+        assert_eq!(hash.format(), "krb5tgs-aes128");
+        assert!(hash.verify(b"Summer2024!"));
+        assert!(!hash.verify(b"Summer2023!"));
+    }
+
+    #[test]
+    fn tgs_aes256() {
+        let hash = Kerberos::parse("$krb5tgs$18$svc_sql$BLACKHAT.LAB$*MSSQLSvc/db.blackhat.lab*$6c0b334edcff88505c3964eb$3439d1c9e3ad40c7a250889f5896e38c3f3b69d5b4972f8e2390fafab655aba1b60a566fbb867c49ffd51cab3eb66e58f5e69ea1f2adc831ccb8bfcb56160de6eb19ff9d9d47e9f9134c0030d83998292fa251").unwrap();
+        assert_eq!(hash.format(), "krb5tgs-aes256");
+        assert!(hash.verify(b"Summer2024!"));
+        assert!(!hash.verify(b"Summer2023!"));
+    }
+}
@@ -0,0 +1,192 @@
+//! LDAP `userPassword` values (`{SHA}`, `{SSHA}`, `{SSHA256}`, `{SSHA512}`, `{MD5}`, `{SMD5}`).
+
+use crate::{hashes::Hash, Error};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use md5::Md5;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+#[derive(Clone, Copy)]
+enum Scheme {
+    Sha,
+    Ssha,
+    Ssha256,
+    Ssha512,
+    Md5,
+    Smd5,
+}
+
+impl Scheme {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "SHA" => Some(Scheme::Sha),
+            "SSHA" => Some(Scheme::Ssha),
+            "SSHA256" => Some(Scheme::Ssha256),
+            "SSHA512" => Some(Scheme::Ssha512),
+            "MD5" => Some(Scheme::Md5),
+            "SMD5" => Some(Scheme::Smd5),
+            _ => None,
+        }
+    }
+
+    fn digest_len(self) -> usize {
+        match self {
+            Scheme::Sha | Scheme::Ssha => 20,
+            Scheme::Ssha256 => 32,
+            Scheme::Ssha512 => 64,
+            Scheme::Md5 | Scheme::Smd5 => 16,
+        }
+    }
+
+    fn is_salted(self) -> bool {
+        matches!(self, Scheme::Ssha | Scheme::Ssha256 | Scheme::Ssha512 | Scheme::Smd5)
+    }
+}
+
+pub struct Ldap {
+    scheme: Scheme,
+    digest: Vec<u8>,
+    salt: Vec<u8>,
+}
+
+impl Ldap {
+    pub fn matches(hash: &str) -> bool {
+        hash.starts_with('{')
+            && hash
+                .split_once('}')
+                .is_some_and(|(name, _)| Scheme::from_name(&name[1..]).is_some())
+    }
+
+    pub fn parse(hash: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidHash(hash.to_string());
+
+        let (name, encoded) = hash.strip_prefix('{').and_then(|rest| rest.split_once('}')).ok_or_else(invalid)?;
+        let scheme = Scheme::from_name(name).ok_or_else(invalid)?;
+        let mut digest = STANDARD.decode(encoded.trim()).map_err(|_| invalid())?;
+
+        let digest_len = scheme.digest_len();
+        if digest.len() < digest_len || (!scheme.is_salted() && digest.len() != digest_len) {
+            return Err(invalid());
+        }
+        let salt = digest.split_off(digest_len);
+
+        Ok(Ldap { scheme, digest, salt })
+    }
+}
+
+impl Hash for Ldap {
+    fn format(&self) -> &'static str {
+        match self.scheme {
+            Scheme::Sha => "ldap-sha",
+            Scheme::Ssha => "ldap-ssha",
+            Scheme::Ssha256 => "ldap-ssha256",
+            Scheme::Ssha512 => "ldap-ssha512",
+            Scheme::Md5 => "ldap-md5",
+            Scheme::Smd5 => "ldap-smd5",
+        }
+    }
+
+    fn label(&self) -> Option<String> {
+        None
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        let digest = match self.scheme {
+            Scheme::Sha | Scheme::Ssha => Sha1::new().chain_update(candidate).chain_update(&self.salt).finalize().to_vec(),
+            Scheme::Ssha256 => Sha256::new().chain_update(candidate).chain_update(&self.salt).finalize().to_vec(),
+            Scheme::Ssha512 => Sha512::new().chain_update(candidate).chain_update(&self.salt).finalize().to_vec(),
+            Scheme::Md5 | Scheme::Smd5 => Md5::new().chain_update(candidate).chain_update(&self.salt).finalize().to_vec(),
+        };
+        digest == self.digest
+    }
+}
+
+// An LDIF file starts with an optional `version:` line followed by `dn:` entries
+pub fn is_ldif(content: &str) -> bool {
+    content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#') && !line.starts_with("version:"))
+        .is_some_and(|line| line.to_ascii_lowercase().starts_with("dn:"))
+}
+
+// Returns the (user, userPassword) pairs of every entry in the LDIF content
+pub fn parse_ldif(content: &str) -> Vec<(String, String)> {
+    let mut passwords = vec![];
+
+    for entry in content.split("\n\n").flat_map(|entry| entry.split("\r\n\r\n")) {
+        // Unfold continuation lines, which start with a single space
+        let mut lines: Vec<String> = vec![];
+        for line in entry.lines() {
+            match (line.strip_prefix(' '), lines.last_mut()) {
+                (Some(continuation), Some(last)) => last.push_str(continuation),
+                _ => lines.push(line.to_string()),
+            }
+        }
+
+        let mut attributes: Vec<(String, String)> = vec![];
+        for line in lines.iter().filter(|line| !line.starts_with('#')) {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = match value.strip_prefix(':') {
+                Some(encoded) => match STANDARD.decode(encoded.trim()) {
+                    Ok(decoded) => String::from_utf8_lossy(&decoded).to_string(),
+                    Err(_) => continue,
+                },
+                None => value.trim().to_string(),
+            };
+            attributes.push((name.trim().to_ascii_lowercase(), value));
+        }
+
+        let user = ["uid", "samaccountname", "cn", "dn"]
+            .iter()
+            .find_map(|name| attributes.iter().find(|(attribute, _)| attribute == name))
+            .map(|(_, value)| value.clone());
+        let Some(user) = user else {
+            continue;
+        };
+
+        passwords.extend(
+            attributes
+                .iter()
+                .filter(|(name, _)| name == "userpassword")
+                .map(|(_, value)| (user.clone(), value.clone())),
+        );
+    }
+
+    passwords
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Ldap;
+    use crate::hashes::Hash;
+
+    #[test]
+    fn schemes() {
+        let hashes = [
+            "{SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=",
+            "{SSHA}ouUZQtFbhkQrfIJ43qx176Wfj4YBAgME",
+            "{SSHA256}HRrSnyMQoWgqgxQEQXacTDCOVmN0BYhOKi2fCgm+aykBAgME",
+            "{SSHA512}dVX3UK1WxAueucUnie+vBKWnUfSCLbiKiy7tj1e+7DvJtas1+7Nu5rO6Hy94i6yVOSdwSg03yOAL7rfuE6WZHAECAwQ=",
+            "{MD5}X03MO1qnZdYdgyfeuILPmQ==",
+            "{smd5}nXuh5+7dhsJMIAfcer5MygECAwQ=",
+        ];
+        for hash in hashes {
+            let hash = Ldap::parse(hash).unwrap();
+            assert!(hash.verify(b"password"));
+            assert!(!hash.verify(b"Password"));
+        }
+    }
+
+    #[test]
+    fn ldif() {
+        let content = "version: 1\n\ndn: uid=jdoe,ou=people,dc=blackhat,dc=lab\nuid: jdoe\nuserPassword:: e1NTSEF9b3VVWlF0RmJoa1FyZklKNDNxeDE3NldmajRZQkF\n nTUU=\n\ndn: cn=admin,dc=blackhat,dc=lab\nuserPassword: {SHA}W6ph5Mm5Pz8GgiULbPgzG37mj9g=\n";
+        assert!(super::is_ldif(content));
+        let passwords = super::parse_ldif(content);
+        assert_eq!(passwords.len(), 2);
+        assert_eq!(passwords[0], ("jdoe".to_string(), "{SSHA}ouUZQtFbhkQrfIJ43qx176Wfj4YBAgME".to_string()));
+        assert_eq!(passwords[1].0, "cn=admin,dc=blackhat,dc=lab");
+    }
+}
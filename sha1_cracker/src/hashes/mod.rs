@@ -0,0 +1,167 @@
+use crate::Error;
+use std::{fs, path::Path};
+
+mod crypt;
+mod htpasswd;
+mod kerberos;
+mod ldap;
+mod mssql;
+mod mysql;
+mod netntlm;
+mod ntlm;
+mod postgres;
+mod sha1;
+mod wpa;
+
+pub use htpasswd::Htpasswd;
+pub use kerberos::{rc4, Kerberos};
+pub use ldap::Ldap;
+pub use mssql::Mssql2012;
+pub use mysql::{MysqlCachingSha2, MysqlNative};
+pub use netntlm::{des_key, NetNtlm};
+pub use ntlm::Ntlm;
+pub use postgres::{PostgresMd5, PostgresScram};
+pub use self::sha1::Sha1;
+pub use wpa::Wpa;
+
+/*
+Trait Declaration for hashes
+
+A hash is a parsed target that candidates from the wordlist are tested against.
+    The Hash trait requires the following methods:
+        - `format`: Returns the name of the hash format (e.g. `sha1`).
+        - `label`: Returns what the hash belongs to (a user, an ESSID...), if known.
+        - `verify`: Takes the bytes of a candidate password and returns true if they produce the hash.
+    It may also implement:
+        - `sha1_digest`: Returns the raw digest of an unsalted SHA-1, so the cracker can hash
+          candidates in batches and look them up instead of calling `verify`.
+*/
+
+pub trait Hash: Send + Sync {
+    fn format(&self) -> &'static str;
+    fn label(&self) -> Option<String>;
+    fn verify(&self, candidate: &[u8]) -> bool;
+
+    fn sha1_digest(&self) -> Option<[u8; 20]> {
+        None
+    }
+}
+
+/*
+UserHash wraps a hash that was given as `user:hash` (database dumps, passwd-like files...)
+so the user is reported even though the format itself does not carry it.
+*/
+
+struct UserHash {
+    user: String,
+    hash: Box<dyn Hash>,
+}
+
+impl Hash for UserHash {
+    fn format(&self) -> &'static str {
+        self.hash.format()
+    }
+
+    fn label(&self) -> Option<String> {
+        Some(self.user.clone())
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        self.hash.verify(candidate)
+    }
+
+    fn sha1_digest(&self) -> Option<[u8; 20]> {
+        self.hash.sha1_digest()
+    }
+}
+
+/*
+Function definitions for hashes
+    - `parse`: Detects the format of a single hash line and returns the matching Hash.
+    - `read_lines`: Returns the hash lines of a file, either one per line or the userPassword values of an LDIF export.
+    - `detect`: Returns the Hash for a bare hash, or None if no format recognises it.
+    - `utf16le`: Encodes a candidate for the formats that hash UTF-16LE.
+*/
+
+pub fn parse(line: &str) -> Result<Box<dyn Hash>, Error> {
+    let line = line.trim();
+
+    if let Some(hash) = detect(line, None) {
+        return hash;
+    }
+    if Ntlm::matches_pwdump(line) {
+        return Ok(Box::new(Ntlm::parse_pwdump(line)?));
+    }
+    if let Some((user, hash)) = line.split_once(':') {
+        if let Some(hash) = detect(hash, Some(user)) {
+            return hash;
+        }
+    }
+
+    Err(Error::InvalidHash(line.to_string()))
+}
+
+pub fn read_lines(path: &Path) -> Result<Vec<String>, Error> {
+    let content = fs::read_to_string(path)?;
+
+    let lines: Vec<String> = if ldap::is_ldif(&content) {
+        ldap::parse_ldif(&content)
+            .into_iter()
+            .map(|(user, password)| format!("{}:{}", user, password))
+            .collect()
+    } else {
+        content.lines().map(str::to_string).collect()
+    };
+
+    Ok(lines.into_iter().filter(|line| !line.trim().is_empty()).collect())
+}
+
+fn detect(hash: &str, user: Option<&str>) -> Option<Result<Box<dyn Hash>, Error>> {
+    fn boxed<H: Hash + 'static>(hash: H) -> Box<dyn Hash> {
+        Box::new(hash)
+    }
+
+    let parsed = if hash.starts_with(wpa::PREFIX) {
+        Wpa::parse(hash).map(boxed)
+    } else if Kerberos::matches(hash) {
+        Kerberos::parse(hash).map(boxed)
+    } else if NetNtlm::matches(hash) {
+        NetNtlm::parse(hash).map(boxed)
+    } else if MysqlNative::matches(hash) {
+        MysqlNative::parse(hash).map(boxed)
+    } else if MysqlCachingSha2::matches(hash) {
+        MysqlCachingSha2::parse(hash).map(boxed)
+    } else if PostgresMd5::matches(hash) {
+        PostgresMd5::parse(hash, user).map(boxed)
+    } else if PostgresScram::matches(hash) {
+        PostgresScram::parse(hash).map(boxed)
+    } else if Ldap::matches(hash) {
+        Ldap::parse(hash).map(boxed)
+    } else if Htpasswd::matches(hash) {
+        Htpasswd::parse(hash).map(boxed)
+    } else if Mssql2012::matches(hash) {
+        Mssql2012::parse(hash).map(boxed)
+    } else if Ntlm::matches(hash) {
+        Ntlm::parse(hash).map(boxed)
+    } else if hash.len() == sha1::SHA1_HEX_LEN {
+        Sha1::parse(hash).map(boxed)
+    } else {
+        return None;
+    };
+
+    Some(parsed.map(|hash| match user {
+        Some(user) if hash.label().is_none() => Box::new(UserHash {
+            user: user.to_string(),
+            hash,
+        }) as Box<dyn Hash>,
+        _ => hash,
+    }))
+}
+
+// A candidate that is not UTF-8 is taken as Latin-1, whose bytes are the first 256 code points
+pub fn utf16le(candidate: &[u8]) -> Vec<u8> {
+    match std::str::from_utf8(candidate) {
+        Ok(text) => text.encode_utf16().flat_map(|unit| unit.to_le_bytes()).collect(),
+        Err(_) => candidate.iter().flat_map(|byte| [*byte, 0]).collect(),
+    }
+}
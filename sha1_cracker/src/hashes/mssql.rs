@@ -0,0 +1,68 @@
+//! Microsoft SQL Server 2012+ password hashes (`sys.sql_logins`).
+
+use crate::{
+    hashes::{utf16le, Hash},
+    Error,
+};
+use sha2::{Digest, Sha512};
+
+const PREFIX: &str = "0x0200";
+const SALT_LEN: usize = 4;
+const DIGEST_LEN: usize = 64;
+
+pub struct Mssql2012 {
+    salt: Vec<u8>,
+    digest: Vec<u8>,
+}
+
+impl Mssql2012 {
+    pub fn matches(hash: &str) -> bool {
+        hash.len() == PREFIX.len() + 2 * (SALT_LEN + DIGEST_LEN)
+            && hash.get(..PREFIX.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(PREFIX))
+    }
+
+    pub fn parse(hash: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidHash(hash.to_string());
+        let mut bytes = hex::decode(hash.get(PREFIX.len()..).ok_or_else(invalid)?).map_err(|_| invalid())?;
+        let digest = bytes.split_off(SALT_LEN);
+        Ok(Mssql2012 { salt: bytes, digest })
+    }
+}
+
+impl Hash for Mssql2012 {
+    fn format(&self) -> &'static str {
+        "mssql2012"
+    }
+
+    fn label(&self) -> Option<String> {
+        None
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        let digest = Sha512::new().chain_update(utf16le(candidate)).chain_update(&self.salt).finalize();
+        digest.as_slice() == self.digest.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Mssql2012;
+    use crate::hashes::Hash;
+
+    #[test]
+    fn mssql2012() {
+        let line = "0x0200A1B2C3D467BCA994E41758B11DF144CACFB7015A3F821F2256E92FCB7AD0158AA1E4A619450F8CEC595C3F709746A1FD862E7A6374F94C9E0F0E58321C45C9EC8B2337AB";
+        assert!(Mssql2012::matches(line));
+        let hash = Mssql2012::parse(line).unwrap();
+        assert!(hash.verify(b"password"));
+        assert!(!hash.verify(b"Password"));
+    }
+
+    #[test]
+    fn multibyte() {
+        // `é` straddles the end of the prefix, the hash has the right length in bytes
+        let line = format!("0x020é{}", "0".repeat(135));
+        assert!(!Mssql2012::matches(&line));
+        assert!(Mssql2012::parse(&line).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+//! MySQL password hashes.
+
+use crate::{
+    hashes::{crypt, Hash},
+    Error,
+};
+use sha1::{Digest, Sha1};
+
+const NATIVE_HEX_LEN: usize = 40;
+const SALT_LEN: usize = 20;
+const DIGEST_LEN: usize = 43;
+
+pub struct MysqlNative {
+    hash: Vec<u8>,
+}
+
+impl MysqlNative {
+    pub fn matches(hash: &str) -> bool {
+        hash.len() == NATIVE_HEX_LEN + 1 && hash.starts_with('*')
+    }
+
+    pub fn parse(hash: &str) -> Result<Self, Error> {
+        let hash = hex::decode(&hash[1..]).map_err(|_| Error::InvalidHash(hash.to_string()))?;
+        Ok(MysqlNative { hash })
+    }
+}
+
+impl Hash for MysqlNative {
+    fn format(&self) -> &'static str {
+        "mysql-native"
+    }
+
+    fn label(&self) -> Option<String> {
+        None
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        Sha1::digest(Sha1::digest(candidate)).as_slice() == self.hash.as_slice()
+    }
+}
+
+pub struct MysqlCachingSha2 {
+    salt: Vec<u8>,
+    rounds: u32,
+    digest: String,
+}
+
+impl MysqlCachingSha2 {
+    pub fn matches(hash: &str) -> bool {
+        hash.starts_with("$mysql$A$") || hash.starts_with("$A$")
+    }
+
+    pub fn parse(hash: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidHash(hash.to_string());
+
+        let (rounds, salt, digest) = if let Some(rest) = hash.strip_prefix("$mysql$A$") {
+            let mut fields = rest.split('*');
+            let (rounds, salt, digest) = match (fields.next(), fields.next(), fields.next(), fields.next()) {
+                (Some(rounds), Some(salt), Some(digest), None) => (rounds, salt, digest),
+                _ => return Err(invalid()),
+            };
+            let salt = hex::decode(salt).map_err(|_| invalid())?;
+            let digest = hex::decode(digest).map_err(|_| invalid())?;
+            (rounds, salt, digest)
+        } else {
+            let rest = hash.strip_prefix("$A$").ok_or_else(invalid)?;
+            let (rounds, rest) = rest.split_once('$').ok_or_else(invalid)?;
+            if rest.len() != SALT_LEN + DIGEST_LEN {
+                return Err(invalid());
+            }
+            let (salt, digest) = rest.as_bytes().split_at(SALT_LEN);
+            (rounds, salt.to_vec(), digest.to_vec())
+        };
+
+        // The field counts thousands of rounds
+        let rounds = rounds
+            .parse::<u32>()
+            .ok()
+            .and_then(|rounds| rounds.checked_mul(1000))
+            .ok_or_else(invalid)?;
+        let digest = String::from_utf8(digest).map_err(|_| invalid())?;
+        if rounds == 0 || salt.len() != SALT_LEN || digest.len() != DIGEST_LEN {
+            return Err(invalid());
+        }
+
+        Ok(MysqlCachingSha2 {
+            salt,
+            rounds,
+            digest,
+        })
+    }
+}
+
+impl Hash for MysqlCachingSha2 {
+    fn format(&self) -> &'static str {
+        "mysql-caching-sha2"
+    }
+
+    fn label(&self) -> Option<String> {
+        None
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        crypt::sha256_crypt(candidate, &self.salt, self.rounds) == self.digest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MysqlCachingSha2, MysqlNative};
+    use crate::hashes::Hash;
+
+    #[test]
+    fn native() {
+        let hash = MysqlNative::parse("*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19").unwrap();
+        assert!(hash.verify(b"password"));
+        assert!(!hash.verify(b"Password"));
+    }
+
+    #[test]
+    fn caching_sha2() {
+        let hash = MysqlCachingSha2::parse("$mysql$A$005*F9CC98CE08892924F50A213B6BC571A2C11778C5*625479393559393965414D45316477456B484F41316E64484742577A2E3162785353526B7554584647562F").unwrap();
+        assert!(hash.verify(b"hashcat"));
+        assert!(!hash.verify(b"hashcat1"));
+    }
+
+    #[test]
+    fn caching_sha2_rounds_overflow() {
+        let hash = "$mysql$A$5000000*F9CC98CE08892924F50A213B6BC571A2C11778C5*625479393559393965414D45316477456B484F41316E64484742577A2E3162785353526B7554584647562F";
+        assert!(matches!(MysqlCachingSha2::parse(hash), Err(crate::Error::InvalidHash(_))));
+    }
+}
@@ -0,0 +1,201 @@
+//! NetNTLMv1 and NetNTLMv2 challenge-responses as captured by Responder.
+
+use crate::{
+    hashes::{utf16le, Hash},
+    Error,
+};
+use des::{
+    cipher::{BlockEncrypt, KeyInit},
+    Des,
+};
+use hmac::{Hmac, Mac};
+use md4::Md4;
+use md5::{Digest, Md5};
+
+enum Response {
+    V1 {
+        // Challenge the NT response was computed over (already mixed with the client
+        // challenge when extended session security is used)
+        challenge: Vec<u8>,
+        nt_response: Vec<u8>,
+    },
+    V2 {
+        // UTF-16LE of uppercase(user) + domain, the HMAC input for the NTLMv2 key
+        identity: Vec<u8>,
+        challenge: Vec<u8>,
+        nt_proof: Vec<u8>,
+        blob: Vec<u8>,
+    },
+}
+
+pub struct NetNtlm {
+    user: String,
+    domain: String,
+    response: Response,
+}
+
+impl NetNtlm {
+    // Cheap check used by `hashes::parse` to route the line here
+    pub fn matches(line: &str) -> bool {
+        let fields: Vec<&str> = line.split(':').collect();
+        fields.len() == 6 && fields[1].is_empty() && !fields[0].is_empty()
+    }
+
+    pub fn parse(line: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidHash(line.to_string());
+        let decode = |field: &str| hex::decode(field).map_err(|_| invalid());
+
+        let fields: Vec<&str> = line.split(':').collect();
+        if !Self::matches(line) {
+            return Err(invalid());
+        }
+        let user = fields[0].to_string();
+        let domain = fields[2].to_string();
+
+        let response = match (fields[3].len(), fields[4].len(), fields[5].len()) {
+            (48, 48, 16) => {
+                let lm_response = decode(fields[3])?;
+                let nt_response = decode(fields[4])?;
+                let server_challenge = decode(fields[5])?;
+
+                // With extended session security the LM field holds the client challenge
+                // padded with zeros, and the effective challenge is derived from both
+                let challenge = if lm_response[8..].iter().all(|byte| *byte == 0) {
+                    let mut md5 = Md5::new();
+                    md5.update(&server_challenge);
+                    md5.update(&lm_response[..8]);
+                    md5.finalize()[..8].to_vec()
+                } else {
+                    server_challenge
+                };
+
+                Response::V1 {
+                    challenge,
+                    nt_response,
+                }
+            }
+            (16, 32, blob_len) if blob_len > 0 => Response::V2 {
+                identity: utf16le((user.to_uppercase() + &domain).as_bytes()),
+                challenge: decode(fields[3])?,
+                nt_proof: decode(fields[4])?,
+                blob: decode(fields[5])?,
+            },
+            _ => return Err(invalid()),
+        };
+
+        Ok(NetNtlm {
+            user,
+            domain,
+            response,
+        })
+    }
+}
+
+impl Hash for NetNtlm {
+    fn format(&self) -> &'static str {
+        match self.response {
+            Response::V1 { .. } => "netntlmv1",
+            Response::V2 { .. } => "netntlmv2",
+        }
+    }
+
+    fn label(&self) -> Option<String> {
+        Some(format!("{}\\{}", self.domain, self.user))
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        let nt_hash = nt_hash(candidate);
+
+        match &self.response {
+            Response::V1 {
+                challenge,
+                nt_response,
+            } => {
+                let mut key = [0u8; 21];
+                key[..16].copy_from_slice(&nt_hash);
+
+                key.chunks(7)
+                    .zip(nt_response.chunks(8))
+                    .all(|(key, expected)| des_encrypt(key, challenge) == expected)
+            }
+            Response::V2 {
+                identity,
+                challenge,
+                nt_proof,
+                blob,
+            } => {
+                let mut mac = hmac_md5(&nt_hash);
+                mac.update(identity);
+                let ntlmv2_key = mac.finalize().into_bytes();
+
+                let mut mac = hmac_md5(&ntlmv2_key);
+                mac.update(challenge);
+                mac.update(blob);
+                mac.finalize().into_bytes().as_slice() == nt_proof.as_slice()
+            }
+        }
+    }
+}
+
+// MD4 of the UTF-16LE encoded password
+pub fn nt_hash(password: &[u8]) -> [u8; 16] {
+    Md4::digest(utf16le(password)).into()
+}
+
+fn hmac_md5(key: &[u8]) -> Hmac<Md5> {
+    <Hmac<Md5> as Mac>::new_from_slice(key).expect("hmac accepts any key length")
+}
+
+// Spread a 7 byte key over the 8 bytes DES expects (the parity bits are ignored)
+pub fn des_key(key: &[u8]) -> [u8; 8] {
+    let mut expanded = [0u8; 8];
+    expanded[0] = key[0];
+    for i in 1..7 {
+        expanded[i] = (key[i - 1] << (8 - i)) | (key[i] >> i);
+    }
+    expanded[7] = key[6] << 1;
+    expanded
+}
+
+fn des_encrypt(key: &[u8], block: &[u8]) -> [u8; 8] {
+    let cipher = Des::new_from_slice(&des_key(key)).expect("des key is 8 bytes");
+    let mut block = des::cipher::generic_array::GenericArray::clone_from_slice(block);
+    cipher.encrypt_block(&mut block);
+    block.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NetNtlm;
+    use crate::hashes::Hash;
+
+    #[test]
+    fn netntlmv2() {
+        let hash = NetNtlm::parse("admin::N46iSNekpT:08ca45b7d7ea58ee:88dcbe4446168966a153a0064958dac6:5c7830315c7830310000000000000b45c67103d07d7b95acd12ffa11230e0000000052920b85f78d013c31cdb3b92f5d765c783030").unwrap();
+        assert_eq!(hash.format(), "netntlmv2");
+        assert_eq!(hash.label().unwrap(), "N46iSNekpT\\admin");
+        assert!(hash.verify(b"hashcat"));
+        assert!(!hash.verify(b"hashcat1"));
+    }
+
+    #[test]
+    fn netntlmv1_ess() {
+        let hash = NetNtlm::parse("u4-netntlm::kNS:338d08f8e26de93300000000000000000000000000000000:9526fb8c23a90751cdd619b6cea564742e1e4bf33006ba41:cb8086049ec4736c").unwrap();
+        assert_eq!(hash.format(), "netntlmv1");
+        assert!(hash.verify(b"hashcat"));
+        assert!(!hash.verify(b"hashcat1"));
+    }
+
+    #[test]
+    fn nt_hash() {
+        assert_eq!(hex::encode(super::nt_hash(b"password")), "8846f7eaee8fb117ad06bdd830b7586c");
+        // Bytes that are not UTF-8 are Latin-1, so both spellings of `café` are the same password
+        assert_eq!(super::nt_hash("café".as_bytes()), super::nt_hash(b"caf\xe9"));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(NetNtlm::parse("admin::N46iSNekpT:08ca45b7d7ea58ee:88dcbe44:").is_err());
+        assert!(NetNtlm::parse("admin:x:N46iSNekpT:a:b:c").is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! Raw NTLM hashes: the hex of MD4(UTF-16LE(password)).
+
+use crate::{
+    hashes::{netntlm::nt_hash, Hash},
+    Error,
+};
+
+const NT_HEX_LEN: usize = 32;
+
+pub struct Ntlm {
+    user: Option<String>,
+    hash: Vec<u8>,
+}
+
+impl Ntlm {
+    pub fn matches(hash: &str) -> bool {
+        hash.len() == NT_HEX_LEN && hash.chars().all(|c| c.is_ascii_hexdigit())
+    }
+
+    pub fn matches_pwdump(line: &str) -> bool {
+        let fields: Vec<&str> = line.split(':').collect();
+        fields.len() == 7 && fields[1].parse::<u32>().is_ok() && Self::matches(fields[3])
+    }
+
+    pub fn parse(hash: &str) -> Result<Self, Error> {
+        if !Self::matches(hash) {
+            return Err(Error::InvalidHash(hash.to_string()));
+        }
+        Ok(Ntlm {
+            user: None,
+            hash: hex::decode(hash).map_err(|_| Error::InvalidHash(hash.to_string()))?,
+        })
+    }
+
+    pub fn parse_pwdump(line: &str) -> Result<Self, Error> {
+        if !Self::matches_pwdump(line) {
+            return Err(Error::InvalidHash(line.to_string()));
+        }
+        let fields: Vec<&str> = line.split(':').collect();
+        let mut ntlm = Self::parse(fields[3])?;
+        ntlm.user = Some(format!("{} ({})", fields[0], fields[1]));
+        Ok(ntlm)
+    }
+}
+
+impl Hash for Ntlm {
+    fn format(&self) -> &'static str {
+        "ntlm"
+    }
+
+    fn label(&self) -> Option<String> {
+        self.user.clone()
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        nt_hash(candidate).as_slice() == self.hash.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Ntlm;
+    use crate::hashes::Hash;
+
+    #[test]
+    fn ntlm() {
+        let hash = Ntlm::parse("8846F7EAEE8FB117AD06BDD830B7586C").unwrap();
+        assert_eq!(hash.format(), "ntlm");
+        assert!(hash.label().is_none());
+        assert!(hash.verify(b"password"));
+        assert!(!hash.verify(b"Password"));
+        assert!(Ntlm::parse("8846f7eaee8fb117ad06bdd830b7586").is_err());
+    }
+
+    #[test]
+    fn pwdump() {
+        let line = "Administrator:500:aad3b435b51404eeaad3b435b51404ee:8846f7eaee8fb117ad06bdd830b7586c:::";
+        assert!(Ntlm::matches_pwdump(line));
+        let hash = Ntlm::parse_pwdump(line).unwrap();
+        assert_eq!(hash.label().unwrap(), "Administrator (500)");
+        assert!(hash.verify(b"password"));
+
+        assert!(!Ntlm::matches_pwdump("Administrator:rid:aad3b435b51404eeaad3b435b51404ee:8846f7eaee8fb117ad06bdd830b7586c:::"));
+        assert!(Ntlm::parse_pwdump("Administrator:500:8846f7eaee8fb117ad06bdd830b7586c").is_err());
+    }
+}
@@ -0,0 +1,124 @@
+//! PostgreSQL password verifiers from `pg_authid` / `pg_shadow`.
+
+use crate::{hashes::Hash, Error};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use sha2::{Digest, Sha256};
+
+const MD5_PREFIX: &str = "md5";
+const MD5_HEX_LEN: usize = 32;
+const SCRAM_PREFIX: &str = "SCRAM-SHA-256$";
+
+pub struct PostgresMd5 {
+    user: String,
+    hash: Vec<u8>,
+}
+
+impl PostgresMd5 {
+    pub fn matches(hash: &str) -> bool {
+        hash.len() == MD5_PREFIX.len() + MD5_HEX_LEN && hash.starts_with(MD5_PREFIX)
+    }
+
+    pub fn parse(hash: &str, user: Option<&str>) -> Result<Self, Error> {
+        let invalid = || Error::InvalidHash(hash.to_string());
+        // The username is the salt, without it the hash cannot be verified
+        let user = user.ok_or_else(invalid)?.to_string();
+        let hash = hex::decode(&hash[MD5_PREFIX.len()..]).map_err(|_| invalid())?;
+        Ok(PostgresMd5 { user, hash })
+    }
+}
+
+impl Hash for PostgresMd5 {
+    fn format(&self) -> &'static str {
+        "postgres-md5"
+    }
+
+    fn label(&self) -> Option<String> {
+        Some(self.user.clone())
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        let digest = Md5::new()
+            .chain_update(candidate)
+            .chain_update(self.user.as_bytes())
+            .finalize();
+        digest.as_slice() == self.hash.as_slice()
+    }
+}
+
+pub struct PostgresScram {
+    iterations: u32,
+    salt: Vec<u8>,
+    stored_key: Vec<u8>,
+}
+
+impl PostgresScram {
+    pub fn matches(hash: &str) -> bool {
+        hash.starts_with(SCRAM_PREFIX)
+    }
+
+    pub fn parse(hash: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidHash(hash.to_string());
+
+        let rest = hash.strip_prefix(SCRAM_PREFIX).ok_or_else(invalid)?;
+        let (params, keys) = rest.split_once('$').ok_or_else(invalid)?;
+        let (iterations, salt) = params.split_once(':').ok_or_else(invalid)?;
+        let (stored_key, _server_key) = keys.split_once(':').ok_or_else(invalid)?;
+
+        let iterations: u32 = iterations.parse().map_err(|_| invalid())?;
+        let salt = STANDARD.decode(salt).map_err(|_| invalid())?;
+        let stored_key = STANDARD.decode(stored_key).map_err(|_| invalid())?;
+        if iterations == 0 || stored_key.len() != 32 {
+            return Err(invalid());
+        }
+
+        Ok(PostgresScram {
+            iterations,
+            salt,
+            stored_key,
+        })
+    }
+}
+
+impl Hash for PostgresScram {
+    fn format(&self) -> &'static str {
+        "postgres-scram-sha256"
+    }
+
+    fn label(&self) -> Option<String> {
+        None
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        let mut salted_password = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(candidate, &self.salt, self.iterations, &mut salted_password);
+
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&salted_password).expect("hmac accepts any key length");
+        mac.update(b"Client Key");
+        let client_key = mac.finalize().into_bytes();
+
+        Sha256::digest(client_key).as_slice() == self.stored_key.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PostgresMd5, PostgresScram};
+    use crate::hashes::Hash;
+
+    #[test]
+    fn md5() {
+        let hash = PostgresMd5::parse("md532e12f215ba27cb750c9e093ce4b5127", Some("postgres")).unwrap();
+        assert!(hash.verify(b"password"));
+        assert!(!hash.verify(b"Password"));
+        assert!(PostgresMd5::parse("md532e12f215ba27cb750c9e093ce4b5127", None).is_err());
+    }
+
+    #[test]
+    fn scram_sha256() {
+        let hash = PostgresScram::parse("SCRAM-SHA-256$4096:AAECAwQFBgcICQoLDA0ODw==$4PSH04DiBM59z6mw0gs6x1r6+duXYQ+R0KwGZr+W5/o=:IgPInY95tTazYxnARISZb/eTxuX/JRwWgrM9ByaOUIk=").unwrap();
+        assert!(hash.verify(b"password"));
+        assert!(!hash.verify(b"Password"));
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{hashes::Hash, Error};
+use sha1::Digest;
+
+pub const SHA1_HEX_LEN: usize = 40;
+
+pub struct Sha1 {
+    digest: [u8; 20],
+}
+
+impl Sha1 {
+    pub fn parse(hash: &str) -> Result<Self, Error> {
+        if hash.len() != SHA1_HEX_LEN || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(Error::InvalidHash(hash.to_string()));
+        }
+        let mut digest = [0u8; 20];
+        hex::decode_to_slice(hash, &mut digest).map_err(|_| Error::InvalidHash(hash.to_string()))?;
+        Ok(Sha1 { digest })
+    }
+}
+
+impl Hash for Sha1 {
+    fn format(&self) -> &'static str {
+        "sha1"
+    }
+
+    fn label(&self) -> Option<String> {
+        None
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        sha1::Sha1::digest(candidate).as_slice() == self.digest
+    }
+
+    fn sha1_digest(&self) -> Option<[u8; 20]> {
+        Some(self.digest)
+    }
+}
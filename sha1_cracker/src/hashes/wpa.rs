@@ -0,0 +1,233 @@
+//! WPA/WPA2 PMKID and EAPOL handshake hashes in the hashcat 22000 format.
+
+use crate::{hashes::Hash, Error};
+use hmac::{Hmac, Mac};
+use md5::Md5;
+use sha1::Sha1;
+
+pub const PREFIX: &str = "WPA*";
+
+const PMK_LEN: usize = 32;
+const PBKDF2_ROUNDS: u32 = 4096;
+const PASSPHRASE_MIN_LEN: usize = 8;
+const PASSPHRASE_MAX_LEN: usize = 63;
+
+// Offsets inside an EAPOL-Key frame
+const EAPOL_KEY_INFO: usize = 5;
+const EAPOL_NONCE: usize = 17;
+const EAPOL_MIC: usize = 81;
+const EAPOL_MIN_LEN: usize = 99;
+
+// Key descriptor versions found in the key information field
+const KEY_VERSION_HMAC_MD5: u8 = 1;
+const KEY_VERSION_HMAC_SHA1: u8 = 2;
+
+enum Kind {
+    Pmkid {
+        pmkid: Vec<u8>,
+    },
+    Eapol {
+        mic: Vec<u8>,
+        key_version: u8,
+        // min(AA, SPA) || max(AA, SPA) || min(ANonce, SNonce) || max(ANonce, SNonce)
+        key_data: Vec<u8>,
+        eapol: Vec<u8>,
+    },
+}
+
+pub struct Wpa {
+    kind: Kind,
+    mac_ap: Vec<u8>,
+    mac_client: Vec<u8>,
+    essid: Vec<u8>,
+}
+
+impl Wpa {
+    pub fn parse(line: &str) -> Result<Self, Error> {
+        let invalid = || Error::InvalidHash(line.to_string());
+        let decode = |field: &str| hex::decode(field).map_err(|_| invalid());
+
+        let fields: Vec<&str> = line.split('*').collect();
+        if fields.len() != 9 || fields[0] != "WPA" {
+            return Err(invalid());
+        }
+
+        let mac_ap = decode(fields[3])?;
+        let mac_client = decode(fields[4])?;
+        let essid = decode(fields[5])?;
+        if mac_ap.len() != 6 || mac_client.len() != 6 || essid.is_empty() || essid.len() > 32 {
+            return Err(invalid());
+        }
+
+        let kind = match fields[1] {
+            "01" => {
+                let pmkid = decode(fields[2])?;
+                if pmkid.len() != 16 {
+                    return Err(invalid());
+                }
+                Kind::Pmkid { pmkid }
+            }
+            "02" => {
+                let mic = decode(fields[2])?;
+                let anonce = decode(fields[6])?;
+                let mut eapol = decode(fields[7])?;
+                if mic.len() != 16 || anonce.len() != 32 || eapol.len() < EAPOL_MIN_LEN {
+                    return Err(invalid());
+                }
+
+                let key_version = eapol[EAPOL_KEY_INFO + 1] & 0x07;
+                if key_version != KEY_VERSION_HMAC_MD5 && key_version != KEY_VERSION_HMAC_SHA1 {
+                    return Err(invalid());
+                }
+
+                // The MIC is computed over the frame with its own MIC field zeroed
+                eapol[EAPOL_MIC..EAPOL_MIC + 16].fill(0);
+
+                let snonce = eapol[EAPOL_NONCE..EAPOL_NONCE + 32].to_vec();
+                let (mac_lo, mac_hi) = min_max(&mac_ap, &mac_client);
+                let (nonce_lo, nonce_hi) = min_max(&anonce, &snonce);
+                let key_data = [mac_lo, mac_hi, nonce_lo, nonce_hi].concat();
+
+                Kind::Eapol {
+                    mic,
+                    key_version,
+                    key_data,
+                    eapol,
+                }
+            }
+            _ => return Err(invalid()),
+        };
+
+        Ok(Wpa {
+            kind,
+            mac_ap,
+            mac_client,
+            essid,
+        })
+    }
+
+    fn pmk(&self, passphrase: &[u8]) -> [u8; PMK_LEN] {
+        let mut pmk = [0u8; PMK_LEN];
+        pbkdf2::pbkdf2_hmac::<Sha1>(passphrase, &self.essid, PBKDF2_ROUNDS, &mut pmk);
+        pmk
+    }
+}
+
+impl Hash for Wpa {
+    fn format(&self) -> &'static str {
+        match self.kind {
+            Kind::Pmkid { .. } => "wpa-pmkid",
+            Kind::Eapol { .. } => "wpa-eapol",
+        }
+    }
+
+    fn label(&self) -> Option<String> {
+        Some(String::from_utf8_lossy(&self.essid).to_string())
+    }
+
+    fn verify(&self, candidate: &[u8]) -> bool {
+        if !(PASSPHRASE_MIN_LEN..=PASSPHRASE_MAX_LEN).contains(&candidate.len()) {
+            return false;
+        }
+        let pmk = self.pmk(candidate);
+
+        match &self.kind {
+            Kind::Pmkid { pmkid } => {
+                let mut mac = hmac_sha1(&pmk);
+                mac.update(b"PMK Name");
+                mac.update(&self.mac_ap);
+                mac.update(&self.mac_client);
+                &mac.finalize().into_bytes()[..16] == pmkid.as_slice()
+            }
+            Kind::Eapol {
+                mic,
+                key_version,
+                key_data,
+                eapol,
+            } => {
+                // Only the KCK (first 16 bytes of the PTK) is needed, so a single PRF round is enough
+                let mut prf = hmac_sha1(&pmk);
+                prf.update(b"Pairwise key expansion\0");
+                prf.update(key_data);
+                prf.update(&[0]);
+                let ptk = prf.finalize().into_bytes();
+                let kck = &ptk[..16];
+
+                let computed = if *key_version == KEY_VERSION_HMAC_MD5 {
+                    let mut mac = <Hmac<Md5> as Mac>::new_from_slice(kck).expect("hmac accepts any key length");
+                    mac.update(eapol);
+                    mac.finalize().into_bytes().to_vec()
+                } else {
+                    let mut mac = hmac_sha1(kck);
+                    mac.update(eapol);
+                    mac.finalize().into_bytes().to_vec()
+                };
+                &computed[..16] == mic.as_slice()
+            }
+        }
+    }
+}
+
+fn hmac_sha1(key: &[u8]) -> Hmac<Sha1> {
+    <Hmac<Sha1> as Mac>::new_from_slice(key).expect("hmac accepts any key length")
+}
+
+fn min_max<'a>(a: &'a [u8], b: &'a [u8]) -> (&'a [u8], &'a [u8]) {
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Wpa;
+    use crate::hashes::Hash;
+
+    const PMKID: &str = "WPA*01*e9b73779797c765d6f7901eecf1d74e3*0a1b2c3d4e5f*112233445566*426c61636b4861744c6162***01";
+    const EAPOL_SHA1: &str = "WPA*02*c0868cfa98f7fc4ee021c6e770b1247f*0a1b2c3d4e5f*112233445566*426c61636b4861744c6162*000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f*0103007502010a001000000000000000016465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f808182830000000000000000000000000000000000000000000000000000000000000000c0868cfa98f7fc4ee021c6e770b1247f001630140100000fac040100000fac040100000fac020000*02";
+    const EAPOL_MD5: &str = "WPA*02*69c90e385ab2c7acd82ca2334330c22f*0a1b2c3d4e5f*112233445566*426c61636b4861744c6162*000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f*01030075020109001000000000000000016465666768696a6b6c6d6e6f707172737475767778797a7b7c7d7e7f80818283000000000000000000000000000000000000000000000000000000000000000069c90e385ab2c7acd82ca2334330c22f001630140100000fac040100000fac040100000fac020000*02";
+
+    #[test]
+    fn pmkid() {
+        let hash = Wpa::parse(PMKID).unwrap();
+        assert_eq!(hash.format(), "wpa-pmkid");
+        assert_eq!(hash.label().unwrap(), "BlackHatLab");
+        assert!(hash.verify(b"password123"));
+        assert!(!hash.verify(b"password124"));
+    }
+
+    #[test]
+    fn pmkid_hashcat_example() {
+        let hash = Wpa::parse("WPA*01*2582a8281bf9d4308d6f5731d0e61c61*4604ba734d4e*89acf0e761f4*ed487162465a774bfba60eb603a39f3a***").unwrap();
+        assert!(hash.verify(b"hashcat!"));
+    }
+
+    #[test]
+    fn eapol_hmac_sha1() {
+        let hash = Wpa::parse(EAPOL_SHA1).unwrap();
+        assert_eq!(hash.format(), "wpa-eapol");
+        assert!(hash.verify(b"password123"));
+        assert!(!hash.verify(b"password124"));
+    }
+
+    #[test]
+    fn eapol_hmac_md5() {
+        let hash = Wpa::parse(EAPOL_MD5).unwrap();
+        assert!(hash.verify(b"password123"));
+    }
+
+    #[test]
+    fn passphrase_length() {
+        let hash = Wpa::parse(PMKID).unwrap();
+        assert!(!hash.verify(b"short"));
+        assert!(!hash.verify("a".repeat(64).as_bytes()));
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(Wpa::parse("WPA*03*00*00*00*00***").is_err());
+        assert!(Wpa::parse("WPA*01*e9b7*0a1b2c3d4e5f*112233445566*426c61636b4861744c6162***01").is_err());
+    }
+}
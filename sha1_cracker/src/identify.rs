@@ -0,0 +1,189 @@
+//! Hash identification: ranks the formats a hash could be in.
+
+use crate::hashes;
+
+/*
+A Guess is a plausible format for a hash.
+    - `name`: Human readable name of the format.
+    - `mode`: The format name the cracker uses, None if it is not supported.
+    - `score`: How likely the guess is, from 0 to 100.
+*/
+
+#[derive(Debug, Clone)]
+pub struct Guess {
+    pub name: &'static str,
+    pub mode: Option<&'static str>,
+    pub score: u8,
+}
+
+struct Rule {
+    name: &'static str,
+    mode: Option<&'static str>,
+    score: u8,
+    matches: fn(&str) -> bool,
+}
+
+const PARSED_SCORE: u8 = 100;
+
+const RULES: &[Rule] = &[
+    // Prefixes
+    Rule { name: "WPA PMKID (hashcat 22000)", mode: Some("wpa-pmkid"), score: 90, matches: |h| h.starts_with("WPA*01*") },
+    Rule { name: "WPA EAPOL (hashcat 22000)", mode: Some("wpa-eapol"), score: 90, matches: |h| h.starts_with("WPA*02*") },
+    Rule { name: "Kerberos 5 TGS-REP RC4", mode: Some("krb5tgs-rc4"), score: 90, matches: |h| h.starts_with("$krb5tgs$23$") },
+    Rule { name: "Kerberos 5 TGS-REP AES128", mode: Some("krb5tgs-aes128"), score: 90, matches: |h| h.starts_with("$krb5tgs$17$") },
+    Rule { name: "Kerberos 5 TGS-REP AES256", mode: Some("krb5tgs-aes256"), score: 90, matches: |h| h.starts_with("$krb5tgs$18$") },
+    Rule { name: "Kerberos 5 AS-REP RC4", mode: Some("krb5asrep-rc4"), score: 90, matches: |h| h.starts_with("$krb5asrep$23$") },
+    Rule { name: "Kerberos 5 AS-REP AES", mode: None, score: 90, matches: |h| h.starts_with("$krb5asrep$17$") || h.starts_with("$krb5asrep$18$") },
+    Rule { name: "bcrypt", mode: Some("bcrypt"), score: 90, matches: |h| ["$2a$", "$2b$", "$2y$", "$2x$"].iter().any(|p| h.starts_with(p)) },
+    Rule { name: "Apache MD5 (apr1)", mode: Some("apr1"), score: 90, matches: |h| h.starts_with("$apr1$") },
+    Rule { name: "md5crypt", mode: Some("md5crypt"), score: 90, matches: |h| h.starts_with("$1$") },
+    Rule { name: "sha256crypt", mode: Some("sha256crypt"), score: 90, matches: |h| h.starts_with("$5$") },
+    Rule { name: "sha512crypt", mode: Some("sha512crypt"), score: 90, matches: |h| h.starts_with("$6$") },
+    Rule { name: "yescrypt", mode: None, score: 90, matches: |h| h.starts_with("$y$") },
+    Rule { name: "Argon2", mode: None, score: 90, matches: |h| h.starts_with("$argon2") },
+    Rule { name: "phpass (WordPress, phpBB)", mode: None, score: 90, matches: |h| h.starts_with("$P$") || h.starts_with("$H$") },
+    Rule { name: "Django PBKDF2-SHA256", mode: None, score: 90, matches: |h| h.starts_with("pbkdf2_sha256$") },
+    Rule { name: "Domain Cached Credentials 2", mode: None, score: 90, matches: |h| h.to_ascii_uppercase().starts_with("$DCC2$") },
+    Rule { name: "MySQL caching_sha2_password", mode: Some("mysql-caching-sha2"), score: 90, matches: |h| h.starts_with("$mysql$A$") || h.starts_with("$A$") },
+    Rule { name: "PostgreSQL SCRAM-SHA-256", mode: Some("postgres-scram-sha256"), score: 90, matches: |h| h.starts_with("SCRAM-SHA-256$") },
+    Rule { name: "MSSQL 2012+", mode: Some("mssql2012"), score: 90, matches: |h| h.len() == 142 && h.to_ascii_lowercase().starts_with("0x0200") },
+    Rule { name: "MSSQL 2005", mode: None, score: 90, matches: |h| h.len() == 54 && h.to_ascii_lowercase().starts_with("0x0100") },
+    Rule { name: "LDAP {SHA}", mode: Some("ldap-sha"), score: 90, matches: |h| starts_with_scheme(h, "{SHA}") },
+    Rule { name: "LDAP {SSHA}", mode: Some("ldap-ssha"), score: 90, matches: |h| starts_with_scheme(h, "{SSHA}") },
+    Rule { name: "LDAP {SSHA256}", mode: Some("ldap-ssha256"), score: 90, matches: |h| starts_with_scheme(h, "{SSHA256}") },
+    Rule { name: "LDAP {SSHA512}", mode: Some("ldap-ssha512"), score: 90, matches: |h| starts_with_scheme(h, "{SSHA512}") },
+    Rule { name: "LDAP {MD5}", mode: Some("ldap-md5"), score: 90, matches: |h| starts_with_scheme(h, "{MD5}") },
+    Rule { name: "LDAP {SMD5}", mode: Some("ldap-smd5"), score: 90, matches: |h| starts_with_scheme(h, "{SMD5}") },
+    Rule { name: "MySQL mysql_native_password", mode: Some("mysql-native"), score: 90, matches: |h| h.len() == 41 && h.starts_with('*') && is_hex(&h[1..]) },
+    Rule { name: "PostgreSQL md5 (needs user:hash)", mode: Some("postgres-md5"), score: 80, matches: |h| h.len() == 35 && h.starts_with("md5") && is_hex(&h[3..]) },
+    // Structure
+    Rule { name: "NetNTLMv1", mode: Some("netntlmv1"), score: 70, matches: |h| is_netntlm(h, 48) },
+    Rule { name: "NetNTLMv2", mode: Some("netntlmv2"), score: 70, matches: |h| is_netntlm(h, 16) },
+    Rule { name: "pwdump (user:rid:lm:nt:::)", mode: Some("ntlm"), score: 70, matches: |h| h.split(':').count() == 7 && h.ends_with(":::") },
+    Rule { name: "DES crypt", mode: Some("descrypt"), score: 40, matches: |h| h.len() == 13 && h.bytes().all(is_crypt_char) },
+    // Charset and length
+    Rule { name: "SHA-1", mode: Some("sha1"), score: 50, matches: |h| h.len() == 40 && is_hex(h) },
+    Rule { name: "RIPEMD-160", mode: None, score: 20, matches: |h| h.len() == 40 && is_hex(h) },
+    Rule { name: "MD5", mode: None, score: 50, matches: |h| h.len() == 32 && is_hex(h) },
+    Rule { name: "NTLM", mode: Some("ntlm"), score: 45, matches: |h| h.len() == 32 && is_hex(h) },
+    Rule { name: "LM", mode: None, score: 30, matches: |h| h.len() == 32 && is_hex(h) },
+    Rule { name: "MD4", mode: None, score: 20, matches: |h| h.len() == 32 && is_hex(h) },
+    Rule { name: "MySQL 3.23 (OLD_PASSWORD)", mode: None, score: 30, matches: |h| h.len() == 16 && is_hex(h) },
+    Rule { name: "SHA-224", mode: None, score: 40, matches: |h| h.len() == 56 && is_hex(h) },
+    Rule { name: "SHA-256", mode: None, score: 50, matches: |h| h.len() == 64 && is_hex(h) },
+    Rule { name: "SHA-384", mode: None, score: 50, matches: |h| h.len() == 96 && is_hex(h) },
+    Rule { name: "SHA-512", mode: None, score: 50, matches: |h| h.len() == 128 && is_hex(h) },
+    Rule { name: "Whirlpool", mode: None, score: 20, matches: |h| h.len() == 128 && is_hex(h) },
+    Rule { name: "Base64 encoded SHA-1", mode: None, score: 20, matches: |h| h.len() == 28 && h.ends_with('=') && h.bytes().all(is_base64_char) },
+    Rule { name: "Base64 encoded MD5", mode: None, score: 20, matches: |h| h.len() == 24 && h.ends_with("==") && h.bytes().all(is_base64_char) },
+];
+
+pub fn identify(line: &str) -> Vec<Guess> {
+    let line = line.trim();
+    let mut guesses: Vec<Guess> = vec![];
+
+    // Rules apply to the hash itself, without a `user:` prefix
+    let mut matching: Vec<&Rule> = RULES.iter().filter(|rule| (rule.matches)(line)).collect();
+    if matching.is_empty() {
+        if let Some((_, hash)) = line.split_once(':') {
+            matching = RULES.iter().filter(|rule| (rule.matches)(hash)).collect();
+        }
+    }
+
+    // What the parsers accept is certain, unless it is a bare hex digest that any algorithm of
+    // that length could have produced: it is scored by the rules like the others
+    let bare = is_hex(line) || line.split_once(':').is_some_and(|(_, hash)| is_hex(hash));
+    if let Some(hash) = hashes::parse(line).ok().filter(|_| !bare) {
+        let mode = Some(hash.format());
+        let name = matching
+            .iter()
+            .copied()
+            .chain(RULES.iter())
+            .find(|rule| rule.mode == mode)
+            .map(|rule| rule.name)
+            .unwrap_or(hash.format());
+        guesses.push(Guess {
+            name,
+            mode,
+            score: PARSED_SCORE,
+        });
+    }
+
+    for rule in matching {
+        if guesses.iter().any(|guess| guess.name == rule.name) {
+            continue;
+        }
+        guesses.push(Guess {
+            name: rule.name,
+            mode: rule.mode,
+            score: rule.score,
+        });
+    }
+
+    guesses.sort_by_key(|guess| std::cmp::Reverse(guess.score));
+    guesses
+}
+
+fn is_hex(hash: &str) -> bool {
+    hash.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+fn is_crypt_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'.' || byte == b'/'
+}
+
+fn is_base64_char(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'+' || byte == b'/' || byte == b'='
+}
+
+// user::domain:challenge:ntproofstr:blob or user::domain:lm:nt:challenge
+fn is_netntlm(hash: &str, third_field_len: usize) -> bool {
+    let fields: Vec<&str> = hash.split(':').collect();
+    fields.len() == 6 && fields[1].is_empty() && fields[3].len() == third_field_len
+}
+
+fn starts_with_scheme(hash: &str, scheme: &str) -> bool {
+    hash.len() > scheme.len() && hash.get(..scheme.len()).is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::identify;
+
+    #[test]
+    fn parsed_first() {
+        let guesses = identify("*2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19");
+        assert_eq!(guesses[0].mode, Some("mysql-native"));
+        assert_eq!(guesses[0].score, 100);
+    }
+
+    #[test]
+    fn bare_hex_is_ambiguous() {
+        // MD5 of "123456", which the NTLM parser accepts too
+        let guesses = identify("e10adc3949ba59abbe56e057f20f883e");
+        assert_eq!(guesses[0].name, "MD5");
+        assert!(guesses.iter().all(|guess| guess.score < 100));
+        assert!(guesses.iter().any(|guess| guess.mode == Some("ntlm") && guess.score == 45));
+    }
+
+    #[test]
+    fn prefix() {
+        let guesses = identify("$argon2id$v=19$m=65536,t=3,p=4$c2FsdA$aGFzaA");
+        assert_eq!(guesses.len(), 1);
+        assert_eq!(guesses[0].name, "Argon2");
+    }
+
+    #[test]
+    fn user_prefix() {
+        let guesses = identify("admin:5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8");
+        assert_eq!(guesses[0].mode, Some("sha1"));
+        assert_eq!(guesses[0].score, 50);
+    }
+
+    #[test]
+    fn unknown() {
+        assert!(identify("not a hash").is_empty());
+        // A multi-byte character across the end of a scheme is not a panic
+        assert!(identify("{SHAé}abc").is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+//! Loopback attack: feeds cracked passwords back in as candidates.
+//!
+//! Passwords of the same organisation tend to be variations of each other (Summer2023!,
+//! Summer2024...). Every round mangles the passwords found so far, along with their base word,
+//! and tests them against the hashes that are left. Only the new finds are fed to the next
+//! round, which ends once a round cracks nothing.
+
+use crate::{
+    cracker::{self, Crack, Limits, Outcome, Stop},
+    hashes::{self, Hash},
+    mangle, Error,
+};
+use std::{io::Cursor, time::Instant};
+
+// Seeds are mangled this many at a time, so only their variants are in memory
+const SEEDS_PER_BATCH: usize = 16;
+
+// Rounds of mangling the `seeds` against the hashes of `lines` that are not in `cracks` yet, within
+// the limit and budget of `limits`. The position of the outcome is the number of candidates tested
+pub fn crack(lines: &[String], cracks: &[Crack], seeds: Vec<String>, limits: &Limits) -> Result<Outcome, Error> {
+    let deadline = limits.budget.map(|budget| Instant::now() + budget);
+    let mut cracked = vec![false; lines.len()];
+    for crack in cracks {
+        cracked[crack.index] = true;
+    }
+
+    let mut found = vec![];
+    let mut tested = 0;
+    let mut seeds = seeds;
+    while !seeds.is_empty() && cracked.contains(&false) {
+        let mut next = vec![];
+        let mut remaining = Remaining::of(lines, &cracked)?;
+
+        for batch in seeds.chunks(SEEDS_PER_BATCH) {
+            let limits = Limits {
+                skip: 0,
+                limit: limits.limit.map(|limit| limit.saturating_sub(tested)),
+                budget: deadline.map(|deadline| deadline.saturating_duration_since(Instant::now())),
+            };
+            let outcome = cracker::crack(Cursor::new(candidates(batch)), &remaining.hashes, &limits)?;
+            tested += outcome.position;
+
+            if !outcome.cracks.is_empty() {
+                for crack in outcome.cracks {
+                    let index = remaining.indexes[crack.index];
+                    cracked[index] = true;
+                    next.push(crack.password.clone());
+                    found.push(Crack {
+                        index,
+                        password: crack.password,
+                    });
+                }
+                remaining = Remaining::of(lines, &cracked)?;
+            }
+
+            match outcome.stop {
+                Stop::Limit | Stop::Budget => {
+                    return Ok(Outcome {
+                        cracks: found,
+                        position: tested,
+                        stop: outcome.stop,
+                    })
+                }
+                _ if remaining.hashes.is_empty() => break,
+                _ => (),
+            }
+        }
+        seeds = next;
+    }
+
+    let stop = if cracked.contains(&false) { Stop::Exhausted } else { Stop::AllCracked };
+    Ok(Outcome {
+        cracks: found,
+        position: tested,
+        stop,
+    })
+}
+
+// The hashes that are not cracked yet, `indexes` maps them back to their position in `lines`
+struct Remaining {
+    indexes: Vec<usize>,
+    hashes: Vec<Box<dyn Hash>>,
+}
+
+impl Remaining {
+    fn of(lines: &[String], cracked: &[bool]) -> Result<Self, Error> {
+        let mut remaining = Remaining {
+            indexes: vec![],
+            hashes: vec![],
+        };
+        for (index, line) in lines.iter().enumerate().filter(|(index, _)| !cracked[*index]) {
+            remaining.indexes.push(index);
+            remaining.hashes.push(hashes::parse(line)?);
+        }
+        Ok(remaining)
+    }
+}
+
+// The variants of a batch of seeds, one per line. `$HEX[...]` seeds are decoded first, the ones
+// that are not UTF-8 are mangled as Latin-1 and their variants written back the same way
+fn candidates(seeds: &[String]) -> Vec<u8> {
+    let mut wordlist = vec![];
+    for seed in seeds {
+        let bytes = cracker::candidate(seed);
+        let variants: Vec<Vec<u8>> = match std::str::from_utf8(&bytes) {
+            Ok(text) => mangle::variants(&base_words(text)).into_iter().map(String::into_bytes).collect(),
+            Err(_) => {
+                let text: String = bytes.iter().map(|byte| *byte as char).collect();
+                mangle::variants(&base_words(&text)).iter().filter_map(|variant| latin1(variant)).collect()
+            }
+        };
+        for variant in variants {
+            wordlist.extend(variant);
+            wordlist.push(b'\n');
+        }
+    }
+    wordlist
+}
+
+// None when the text has characters Latin-1 does not have (`ÿ` upper cased is `Ÿ`)
+fn latin1(text: &str) -> Option<Vec<u8>> {
+    text.chars().map(|c| u8::try_from(c).ok()).collect()
+}
+
+// The password itself and what is left once digits and symbols are cut from both ends
+fn base_words(password: &str) -> Vec<String> {
+    let mut words = vec![password.to_string()];
+    let base = password.trim_matches(|c: char| !c.is_alphabetic());
+    if !base.is_empty() && base != password {
+        words.push(base.to_string());
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha1::{Digest, Sha1};
+    use std::time::Duration;
+
+    fn sha1(password: &str) -> String {
+        hex::encode(Sha1::digest(password.as_bytes()))
+    }
+
+    #[test]
+    fn chains_rounds() {
+        // Summer2023 gives Summer2024 in a first round, which gives Summer2024! in the second
+        let lines = vec![sha1("Summer2023"), sha1("Summer2024"), sha1("Summer2024!"), sha1("unrelated")];
+        let cracks = vec![Crack {
+            index: 0,
+            password: "Summer2023".to_string(),
+        }];
+
+        let outcome = crack(&lines, &cracks, vec!["Summer2023".to_string()], &Limits::default()).unwrap();
+        let found: Vec<(usize, &str)> = outcome.cracks.iter().map(|crack| (crack.index, crack.password.as_str())).collect();
+        assert_eq!(found, vec![(1, "Summer2024"), (2, "Summer2024!")]);
+        assert_eq!(outcome.stop, Stop::Exhausted);
+    }
+
+    #[test]
+    fn limits() {
+        let lines = vec![sha1("Summer2024")];
+        let seeds = vec!["Summer2023".to_string()];
+
+        let limits = Limits {
+            limit: Some(10),
+            ..Limits::default()
+        };
+        let outcome = crack(&lines, &[], seeds.clone(), &limits).unwrap();
+        assert_eq!(outcome.stop, Stop::Limit);
+        assert_eq!(outcome.position, 10);
+        assert!(outcome.cracks.is_empty());
+
+        let limits = Limits {
+            budget: Some(Duration::ZERO),
+            ..Limits::default()
+        };
+        let outcome = crack(&lines, &[], seeds, &limits).unwrap();
+        assert_eq!(outcome.stop, Stop::Budget);
+        assert!(outcome.cracks.is_empty());
+    }
+
+    #[test]
+    fn hex_seeds() {
+        // `café` in Latin-1 gives `Café1` in Latin-1 too, not the `$HEX[...]` text with a suffix
+        let lines = vec![hex::encode(Sha1::digest(b"Caf\xe91"))];
+        let outcome = crack(&lines, &[], vec!["$HEX[636166e9]".to_string()], &Limits::default()).unwrap();
+        assert_eq!(outcome.stop, Stop::AllCracked);
+        assert_eq!(outcome.cracks[0].password, "$HEX[436166e931]");
+    }
+
+    #[test]
+    fn base_words_trim_digits_and_symbols() {
+        assert_eq!(base_words("!Winter2024?"), vec!["!Winter2024?", "Winter"]);
+        assert_eq!(base_words("plain"), vec!["plain"]);
+    }
+}
@@ -1,36 +1,356 @@
-use sha1::Digest;
-use std::{
-    env,
-    error::Error,
-    fs::File,
-    io::{BufRead, BufReader},
-};
-
-const SHA1_HEX_LEN: usize = 40;
-
-fn main() -> Result<(), Box<dyn Error>> {
-    let args: Vec<String> = env::args().collect();
-
-    if args.len() != 3 {
-        println!("usage: ./sha1_cracker <wordlist.txt> <sha1_hash>");
-        return Ok(());
-    }
+use std::{error::Error as StdError, process::ExitCode, time::Duration};
 
-    let hash = args[2].trim();
-    if hash.len() != SHA1_HEX_LEN {
-        return Err("sha1 hash is not valid".into());
-    }
+use clap::{value_parser, Arg, ArgMatches, Command};
+use cli::Output;
+use cracker::Limits;
+use distributed::ServerOptions;
+use stats::Policy;
+use wordgen::Filters;
+
+mod error;
+pub use error::Error;
+mod cli;
+mod cracker;
+mod distributed;
+mod extract;
+mod hashes;
+mod identify;
+mod loopback;
+mod mangle;
+mod multi_sha1;
+mod potfile;
+mod single;
+mod stats;
+mod wordgen;
+
+// Exit codes of the commands that crack, see `cli::Status`
+macro_rules! exit_status {
+    () => {
+        "EXIT STATUS:
+    0   At least one password was found
+    1   Every candidate was tested without finding anything
+    2   Error
+    3   Nothing found before --limit or --max-time stopped the run"
+    };
+}
 
-    let wordlist = File::open(&args[1].trim())?;
-    let reader = BufReader::new(&wordlist);
+const SUPPORTED_FORMATS: &str = concat!("SUPPORTED FORMATS:
+    sha1                40 hex characters
+    ntlm                32 hex characters or pwdump lines (user:rid:lm:nt:::)
+    WPA*01* / WPA*02*   hashcat 22000 PMKID and EAPOL lines
+    user::domain:...    NetNTLMv1 / NetNTLMv2 (Responder)
+    $krb5tgs$23$ / $krb5tgs$17$ / $krb5tgs$18$ / $krb5asrep$23$   Kerberoast and AS-REP roast
+    *<40 hex> / $mysql$A$...   MySQL native and caching_sha2 passwords
+    user:md5<32 hex> / SCRAM-SHA-256$...   PostgreSQL md5 and SCRAM verifiers
+    0x0200...           MSSQL 2012+
+    $apr1$ / $1$ / $2y$ / $5$ / $6$ / DES crypt   htpasswd and shadow entries
+    {SHA} / {SSHA} / {SSHA256} / {SSHA512} / {MD5} / {SMD5}   LDAP userPassword values
+
+Hash files may also be LDIF exports, the uid of each entry is kept.
+Any hash may be prefixed with `user:` to keep track of who it belongs to.
+
+", exit_status!());
+
+fn output_arg() -> Arg<'static> {
+    Arg::new("output")
+        .help("How cracked passwords are written to stdout, logs always go to stderr")
+        .long("output")
+        .takes_value(true)
+        .value_parser(Output::NAMES)
+        .default_value("text")
+}
 
-    for line in reader.lines() {
-        let line = line?;
-        if hash == &hex::encode(sha1::Sha1::digest(line.trim().as_bytes())) {
-            println!("Password found: {}", &line.trim());
-            return Ok(());
+fn output(matches: &ArgMatches) -> Output {
+    Output::from_name(matches.get_one::<String>("output").unwrap()).unwrap()
+}
+
+fn main() -> ExitCode {
+    let cli = Command::new(clap::crate_name!())
+        .version(clap::crate_version!())
+        .about("Wordlist based password hash cracker.")
+        .after_help(SUPPORTED_FORMATS)
+        .args_conflicts_with_subcommands(true)
+        .subcommand_negates_reqs(true)
+        .arg(Arg::new("wordlist")
+            .help("The wordlist to test candidates from")
+            .required(true)
+            .index(1)
+        )
+        .arg(Arg::new("hash")
+            .help("A hash, or a file with one hash per line")
+            .required_unless_present("keyspace")
+            .index(2)
+        )
+        .arg(output_arg())
+        .arg(Arg::new("keyspace")
+            .help("Print the number of candidates in the wordlist and exit")
+            .long("keyspace")
+        )
+        .arg(Arg::new("potfile")
+            .help("Append cracked passwords to this file, its passwords also seed --loopback")
+            .long("potfile")
+            .takes_value(true)
+        )
+        .arg(Arg::new("loopback")
+            .help("Mangle cracked passwords against the remaining hashes until nothing new is found")
+            .long("loopback")
+        )
+        .arg(Arg::new("skip")
+            .help("Start at this candidate index")
+            .long("skip")
+            .takes_value(true)
+            .value_parser(value_parser!(u64))
+            .default_value("0")
+        )
+        .arg(Arg::new("limit")
+            .help("Stop after testing this many candidates")
+            .long("limit")
+            .takes_value(true)
+            .value_parser(value_parser!(u64))
+        )
+        .arg(Arg::new("max-time")
+            .help("Stop after this many seconds")
+            .long("max-time")
+            .takes_value(true)
+            .value_parser(value_parser!(u64))
+        )
+        .subcommand(Command::new("extract")
+            .about("Extract NT hashes from offline SAM and SYSTEM registry hives")
+            .after_help(exit_status!())
+            .arg(Arg::new("sam")
+                .help("The SAM hive file")
+                .required(true)
+                .long("sam")
+                .takes_value(true)
+            )
+            .arg(Arg::new("system")
+                .help("The SYSTEM hive file, which holds the boot key")
+                .required(true)
+                .long("system")
+                .takes_value(true)
+            )
+            .arg(Arg::new("wordlist")
+                .help("Crack the extracted hashes with this wordlist")
+                .short('w')
+                .long("wordlist")
+                .takes_value(true)
+            )
+            .arg(output_arg())
+        )
+        .subcommand(Command::new("identify")
+            .about("List the formats a hash could be in, most likely first")
+            .arg(Arg::new("hash")
+                .help("A hash, or a file with one hash per line")
+                .required(true)
+            )
+        )
+        .subcommand(Command::new("single")
+            .about("Guess passwords from each user's login, full name and home directory")
+            .after_help(exit_status!())
+            .arg(Arg::new("hash")
+                .help("A `user:hash:gecos` or passwd line, or a file with one per line")
+                .required(true)
+            )
+            .arg(output_arg())
+        )
+        .subcommand(Command::new("stats")
+            .about("Analyse cracked passwords from a potfile or a list of plaintexts")
+            .arg(Arg::new("file")
+                .help("A potfile, or a file with one password per line")
+                .required(true)
+            )
+            .arg(Arg::new("min-length")
+                .help("Minimum length of the password policy")
+                .long("min-length")
+                .takes_value(true)
+                .value_parser(value_parser!(usize))
+                .default_value("8")
+            )
+            .arg(Arg::new("min-classes")
+                .help("Minimum number of character classes of the password policy")
+                .long("min-classes")
+                .takes_value(true)
+                .value_parser(value_parser!(usize))
+                .default_value("3")
+            )
+            .arg(Arg::new("top")
+                .help("Number of entries shown per list")
+                .long("top")
+                .takes_value(true)
+                .value_parser(value_parser!(usize))
+                .default_value("10")
+            )
+        )
+        .subcommand(Command::new("generate")
+            .about("Build a wordlist from a client's HTML and text files")
+            .arg(Arg::new("paths")
+                .help("Files, or directories such as a website mirror")
+                .required(true)
+                .multiple_values(true)
+            )
+            .arg(Arg::new("output")
+                .help("Write the wordlist to this file instead of stdout")
+                .short('o')
+                .long("output")
+                .takes_value(true)
+            )
+            .arg(Arg::new("min-length")
+                .help("Shortest word to keep")
+                .long("min-length")
+                .takes_value(true)
+                .value_parser(value_parser!(usize))
+                .default_value("4")
+            )
+            .arg(Arg::new("max-length")
+                .help("Longest word to keep")
+                .long("max-length")
+                .takes_value(true)
+                .value_parser(value_parser!(usize))
+                .default_value("32")
+            )
+            .arg(Arg::new("min-count")
+                .help("Only keep words seen at least this many times")
+                .long("min-count")
+                .takes_value(true)
+                .value_parser(value_parser!(usize))
+                .default_value("1")
+            )
+        )
+        .subcommand(Command::new("server")
+            .about("Share the wordlist between workers connecting over TCP")
+            .after_help(exit_status!())
+            .arg(Arg::new("listen")
+                .help("Address to listen on, use 0.0.0.0:7878 to accept workers from other hosts")
+                .short('l')
+                .long("listen")
+                .takes_value(true)
+                .default_value("127.0.0.1:7878")
+            )
+            .arg(Arg::new("secret")
+                .help("Shared secret workers have to give before they get the hashes")
+                .long("secret")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(Arg::new("unit-size")
+                .help("Approximate size of a work unit, in bytes of wordlist")
+                .long("unit-size")
+                .takes_value(true)
+                .value_parser(value_parser!(u64))
+                .default_value("1048576")
+            )
+            .arg(Arg::new("timeout")
+                .help("Seconds without news from a worker before its unit is reassigned")
+                .long("timeout")
+                .takes_value(true)
+                .value_parser(value_parser!(u64))
+                .default_value("60")
+            )
+            .arg(Arg::new("wordlist")
+                .help("The wordlist to split between workers")
+                .required(true)
+            )
+            .arg(Arg::new("hash")
+                .help("A hash, or a file with one hash per line")
+                .required(true)
+            )
+            .arg(output_arg())
+        )
+        .subcommand(Command::new("worker")
+            .about("Crack work units handed out by a server")
+            .arg(Arg::new("server")
+                .help("Address of the server")
+                .short('s')
+                .long("server")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(Arg::new("secret")
+                .help("Shared secret of the server")
+                .long("secret")
+                .takes_value(true)
+                .required(true)
+            )
+            .arg(Arg::new("name")
+                .help("Name shown in the server logs")
+                .short('n')
+                .long("name")
+                .takes_value(true)
+                .default_value("worker")
+            )
+        )
+        .arg_required_else_help(true)
+        .get_matches();
+
+    match run(&cli) {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Error: {}", err);
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run(cli: &ArgMatches) -> Result<ExitCode, Box<dyn StdError>> {
+    if let Some(matches) = cli.subcommand_matches("extract") {
+        let sam = matches.get_one::<String>("sam").unwrap();
+        let system = matches.get_one::<String>("system").unwrap();
+        let wordlist = matches.get_one::<String>("wordlist");
+        if let Some(status) = cli::extract(sam, system, wordlist.map(String::as_str), output(matches))? {
+            return Ok(status.code());
         }
+    } else if let Some(matches) = cli.subcommand_matches("identify") {
+        let hash = matches.get_one::<String>("hash").unwrap();
+        cli::identify(hash)?;
+    } else if let Some(matches) = cli.subcommand_matches("single") {
+        let hash = matches.get_one::<String>("hash").unwrap();
+        return Ok(cli::single(hash, output(matches))?.code());
+    } else if let Some(matches) = cli.subcommand_matches("stats") {
+        let file = matches.get_one::<String>("file").unwrap();
+        let policy = Policy {
+            min_length: *matches.get_one::<usize>("min-length").unwrap(),
+            min_classes: *matches.get_one::<usize>("min-classes").unwrap(),
+        };
+        cli::stats(file, &policy, *matches.get_one::<usize>("top").unwrap())?;
+    } else if let Some(matches) = cli.subcommand_matches("generate") {
+        let paths: Vec<&String> = matches.get_many::<String>("paths").unwrap().collect();
+        let filters = Filters {
+            min_length: *matches.get_one::<usize>("min-length").unwrap(),
+            max_length: *matches.get_one::<usize>("max-length").unwrap(),
+            min_count: *matches.get_one::<usize>("min-count").unwrap(),
+        };
+        let output = matches.get_one::<String>("output");
+        cli::generate(&paths, &filters, output.map(String::as_str))?;
+    } else if let Some(matches) = cli.subcommand_matches("server") {
+        let wordlist = matches.get_one::<String>("wordlist").unwrap();
+        let hash = matches.get_one::<String>("hash").unwrap();
+        let options = ServerOptions {
+            listen: matches.get_one::<String>("listen").unwrap().to_string(),
+            secret: matches.get_one::<String>("secret").unwrap().to_string(),
+            unit_size: *matches.get_one::<u64>("unit-size").unwrap(),
+            timeout: Duration::from_secs(*matches.get_one::<u64>("timeout").unwrap()),
+        };
+        return Ok(cli::server(wordlist, hash, options, output(matches))?.code());
+    } else if let Some(matches) = cli.subcommand_matches("worker") {
+        let server = matches.get_one::<String>("server").unwrap();
+        let name = matches.get_one::<String>("name").unwrap();
+        let secret = matches.get_one::<String>("secret").unwrap();
+        cli::worker(server, name, secret)?;
+    } else if cli.contains_id("keyspace") {
+        let wordlist = cli.get_one::<String>("wordlist").unwrap();
+        cli::keyspace(wordlist)?;
+    } else {
+        let wordlist = cli.get_one::<String>("wordlist").unwrap();
+        let hash = cli.get_one::<String>("hash").unwrap();
+        let limits = Limits {
+            skip: *cli.get_one::<u64>("skip").unwrap(),
+            limit: cli.get_one::<u64>("limit").copied(),
+            budget: cli.get_one::<u64>("max-time").map(|secs| Duration::from_secs(*secs)),
+        };
+        let potfile = cli.get_one::<String>("potfile");
+        let loopback = cli.contains_id("loopback");
+        return Ok(cli::crack(wordlist, hash, &limits, potfile.map(String::as_str), loopback, output(cli))?.code());
     }
-    println!("Password not found in wordlist :(");
-    Ok(())
+
+    Ok(ExitCode::SUCCESS)
 }
@@ -0,0 +1,80 @@
+//! Word mangling: turns a base word into the variations people actually use as passwords.
+//!
+//! Candidates come out roughly from most to least likely: the case and leet variants of every
+//! word first, then the same with digits, common suffixes and years appended.
+
+use std::collections::HashSet;
+
+const LEET: &[(char, char)] = &[('a', '@'), ('e', '3'), ('i', '1'), ('o', '0'), ('s', '$')];
+const SUFFIXES: &[&str] = &["!", "1!", "123", "1234", "123!"];
+const YEARS: std::ops::RangeInclusive<u32> = 1970..=2030;
+
+// Every variant of every word, without duplicates
+pub fn variants(words: &[String]) -> Vec<String> {
+    let mut bases = vec![];
+    for word in words.iter().filter(|word| !word.is_empty()) {
+        let lower = word.to_lowercase();
+        bases.push(word.clone());
+        bases.push(lower.clone());
+        bases.push(word.to_uppercase());
+        bases.push(capitalize(&lower));
+        bases.push(lower.chars().rev().collect());
+        bases.push(lower.repeat(2));
+        bases.push(leet(&lower));
+        bases.push(capitalize(&leet(&lower)));
+    }
+
+    let suffixes = std::iter::once(String::new())
+        .chain((0..10).map(|digit| digit.to_string()))
+        .chain((0..100).map(|number| format!("{:02}", number)))
+        .chain(SUFFIXES.iter().map(|suffix| suffix.to_string()))
+        .chain(YEARS.map(|year| year.to_string()));
+
+    let mut seen = HashSet::new();
+    let mut candidates = vec![];
+    for suffix in suffixes {
+        for base in &bases {
+            let candidate = format!("{}{}", base, suffix);
+            if seen.insert(candidate.clone()) {
+                candidates.push(candidate);
+            }
+        }
+    }
+    candidates
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn leet(word: &str) -> String {
+    word.chars()
+        .map(|c| LEET.iter().find(|(from, _)| *from == c).map_or(c, |(_, to)| *to))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn variants_of_a_word() {
+        let candidates = variants(&["Smith".to_string()]);
+
+        for expected in ["Smith", "smith", "SMITH", "htims", "smithsmith", "$m1th", "smith2024", "smith1", "Smith07", "smith123", "Smith1987"] {
+            assert!(candidates.contains(&expected.to_string()), "{}", expected);
+        }
+        assert_eq!(candidates[0], "Smith");
+        assert_eq!(candidates.iter().collect::<HashSet<_>>().len(), candidates.len());
+    }
+
+    #[test]
+    fn no_words() {
+        assert!(variants(&[]).is_empty());
+        assert!(variants(&[String::new()]).is_empty());
+    }
+}
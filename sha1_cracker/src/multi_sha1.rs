@@ -0,0 +1,358 @@
+//! Multi-buffer SHA-1: hashes several candidates at once, one per SIMD lane.
+//!
+//! Every lane runs the same compression function on its own message, so 4 (SSE2) or 8 (AVX2)
+//! candidates cost about as much as one. Only single block messages (up to 55 bytes, which is
+//! nearly every password) go through the SIMD path, longer ones fall back to the `sha1` crate.
+//! The backend is picked at runtime from the features of the CPU.
+
+use sha1::{Digest, Sha1};
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+
+pub const DIGEST_LEN: usize = 20;
+
+const H: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+const K: [u32; 4] = [0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xca62c1d6];
+
+// A single 64 byte block holds the message, the 0x80 marker and the 8 byte length
+const MAX_SINGLE_BLOCK_LEN: usize = 55;
+const MAX_WIDTH: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Avx2,
+    Sse2,
+    Scalar,
+}
+
+impl Backend {
+    pub fn detect() -> Self {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return Backend::Avx2;
+            }
+            if is_x86_feature_detected!("sse2") {
+                return Backend::Sse2;
+            }
+        }
+        Backend::Scalar
+    }
+}
+
+// Hash every candidate with the given backend, digests are returned in the same order. A SIMD
+// backend the CPU does not support falls back to the scalar path
+pub fn digest_batch(backend: Backend, candidates: &[&[u8]]) -> Vec<[u8; DIGEST_LEN]> {
+    let mut digests = vec![[0u8; DIGEST_LEN]; candidates.len()];
+    match backend {
+        // Safety: the target feature of each batch function is checked right before the call
+        #[cfg(target_arch = "x86_64")]
+        Backend::Avx2 if is_x86_feature_detected!("avx2") => unsafe { batch_avx2(candidates, &mut digests) },
+        #[cfg(target_arch = "x86_64")]
+        Backend::Sse2 if is_x86_feature_detected!("sse2") => unsafe { batch_sse2(candidates, &mut digests) },
+        _ => {
+            for (candidate, digest) in candidates.iter().zip(digests.iter_mut()) {
+                *digest = scalar(candidate);
+            }
+        }
+    }
+    digests
+}
+
+fn scalar(candidate: &[u8]) -> [u8; DIGEST_LEN] {
+    Sha1::digest(candidate).into()
+}
+
+/*
+Lanes abstracts a vector of 32 bit words, one per message hashed in parallel.
+    - `WIDTH`: Number of lanes.
+    - `load` / `store`: Move one word per lane from / to memory.
+    - the remaining methods are the lane-wise operations the compression function needs.
+*/
+
+trait Lanes: Copy {
+    const WIDTH: usize;
+    fn splat(value: u32) -> Self;
+    fn load(words: &[u32; MAX_WIDTH]) -> Self;
+    fn store(self, words: &mut [u32; MAX_WIDTH]);
+    fn add(self, other: Self) -> Self;
+    fn xor(self, other: Self) -> Self;
+    fn and(self, other: Self) -> Self;
+    fn or(self, other: Self) -> Self;
+    // !self & other
+    fn andnot(self, other: Self) -> Self;
+    fn rotl1(self) -> Self;
+    fn rotl5(self) -> Self;
+    fn rotl30(self) -> Self;
+}
+
+#[cfg(target_arch = "x86_64")]
+#[derive(Clone, Copy)]
+struct Sse2(__m128i);
+
+#[cfg(target_arch = "x86_64")]
+impl Lanes for Sse2 {
+    const WIDTH: usize = 4;
+
+    #[inline(always)]
+    fn splat(value: u32) -> Self {
+        unsafe { Sse2(_mm_set1_epi32(value as i32)) }
+    }
+
+    #[inline(always)]
+    fn load(words: &[u32; MAX_WIDTH]) -> Self {
+        unsafe { Sse2(_mm_loadu_si128(words.as_ptr() as *const __m128i)) }
+    }
+
+    #[inline(always)]
+    fn store(self, words: &mut [u32; MAX_WIDTH]) {
+        unsafe { _mm_storeu_si128(words.as_mut_ptr() as *mut __m128i, self.0) }
+    }
+
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        unsafe { Sse2(_mm_add_epi32(self.0, other.0)) }
+    }
+
+    #[inline(always)]
+    fn xor(self, other: Self) -> Self {
+        unsafe { Sse2(_mm_xor_si128(self.0, other.0)) }
+    }
+
+    #[inline(always)]
+    fn and(self, other: Self) -> Self {
+        unsafe { Sse2(_mm_and_si128(self.0, other.0)) }
+    }
+
+    #[inline(always)]
+    fn or(self, other: Self) -> Self {
+        unsafe { Sse2(_mm_or_si128(self.0, other.0)) }
+    }
+
+    #[inline(always)]
+    fn andnot(self, other: Self) -> Self {
+        unsafe { Sse2(_mm_andnot_si128(self.0, other.0)) }
+    }
+
+    #[inline(always)]
+    fn rotl1(self) -> Self {
+        unsafe { Sse2(_mm_or_si128(_mm_slli_epi32::<1>(self.0), _mm_srli_epi32::<31>(self.0))) }
+    }
+
+    #[inline(always)]
+    fn rotl5(self) -> Self {
+        unsafe { Sse2(_mm_or_si128(_mm_slli_epi32::<5>(self.0), _mm_srli_epi32::<27>(self.0))) }
+    }
+
+    #[inline(always)]
+    fn rotl30(self) -> Self {
+        unsafe { Sse2(_mm_or_si128(_mm_slli_epi32::<30>(self.0), _mm_srli_epi32::<2>(self.0))) }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[derive(Clone, Copy)]
+struct Avx2(__m256i);
+
+#[cfg(target_arch = "x86_64")]
+impl Lanes for Avx2 {
+    const WIDTH: usize = 8;
+
+    #[inline(always)]
+    fn splat(value: u32) -> Self {
+        unsafe { Avx2(_mm256_set1_epi32(value as i32)) }
+    }
+
+    #[inline(always)]
+    fn load(words: &[u32; MAX_WIDTH]) -> Self {
+        unsafe { Avx2(_mm256_loadu_si256(words.as_ptr() as *const __m256i)) }
+    }
+
+    #[inline(always)]
+    fn store(self, words: &mut [u32; MAX_WIDTH]) {
+        unsafe { _mm256_storeu_si256(words.as_mut_ptr() as *mut __m256i, self.0) }
+    }
+
+    #[inline(always)]
+    fn add(self, other: Self) -> Self {
+        unsafe { Avx2(_mm256_add_epi32(self.0, other.0)) }
+    }
+
+    #[inline(always)]
+    fn xor(self, other: Self) -> Self {
+        unsafe { Avx2(_mm256_xor_si256(self.0, other.0)) }
+    }
+
+    #[inline(always)]
+    fn and(self, other: Self) -> Self {
+        unsafe { Avx2(_mm256_and_si256(self.0, other.0)) }
+    }
+
+    #[inline(always)]
+    fn or(self, other: Self) -> Self {
+        unsafe { Avx2(_mm256_or_si256(self.0, other.0)) }
+    }
+
+    #[inline(always)]
+    fn andnot(self, other: Self) -> Self {
+        unsafe { Avx2(_mm256_andnot_si256(self.0, other.0)) }
+    }
+
+    #[inline(always)]
+    fn rotl1(self) -> Self {
+        unsafe { Avx2(_mm256_or_si256(_mm256_slli_epi32::<1>(self.0), _mm256_srli_epi32::<31>(self.0))) }
+    }
+
+    #[inline(always)]
+    fn rotl5(self) -> Self {
+        unsafe { Avx2(_mm256_or_si256(_mm256_slli_epi32::<5>(self.0), _mm256_srli_epi32::<27>(self.0))) }
+    }
+
+    #[inline(always)]
+    fn rotl30(self) -> Self {
+        unsafe { Avx2(_mm256_or_si256(_mm256_slli_epi32::<30>(self.0), _mm256_srli_epi32::<2>(self.0))) }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn batch_sse2(candidates: &[&[u8]], digests: &mut [[u8; DIGEST_LEN]]) {
+    batch::<Sse2>(candidates, digests)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn batch_avx2(candidates: &[&[u8]], digests: &mut [[u8; DIGEST_LEN]]) {
+    batch::<Avx2>(candidates, digests)
+}
+
+#[inline(always)]
+fn batch<V: Lanes>(candidates: &[&[u8]], digests: &mut [[u8; DIGEST_LEN]]) {
+    for (chunk, digests) in candidates.chunks(V::WIDTH).zip(digests.chunks_mut(V::WIDTH)) {
+        // Message words of every lane, lanes without a (short enough) candidate hash nothing
+        let mut blocks = [[0u32; 16]; MAX_WIDTH];
+        for (block, candidate) in blocks.iter_mut().zip(chunk.iter()) {
+            if candidate.len() <= MAX_SINGLE_BLOCK_LEN {
+                *block = pad(candidate);
+            }
+        }
+
+        let mut w = [V::splat(0); 16];
+        for (i, word) in w.iter_mut().enumerate() {
+            let mut words = [0u32; MAX_WIDTH];
+            for (lane, block) in blocks.iter().enumerate().take(V::WIDTH) {
+                words[lane] = block[i];
+            }
+            *word = V::load(&words);
+        }
+
+        let state = compress(w);
+        let mut words = [[0u32; MAX_WIDTH]; 5];
+        for (words, state) in words.iter_mut().zip(state.iter()) {
+            state.store(words);
+        }
+
+        for (lane, (candidate, digest)) in chunk.iter().zip(digests.iter_mut()).enumerate() {
+            if candidate.len() > MAX_SINGLE_BLOCK_LEN {
+                *digest = scalar(candidate);
+                continue;
+            }
+            for (i, words) in words.iter().enumerate() {
+                digest[i * 4..i * 4 + 4].copy_from_slice(&words[lane].to_be_bytes());
+            }
+        }
+    }
+}
+
+// Big endian message words with SHA-1 padding for a message that fits in one block
+fn pad(message: &[u8]) -> [u32; 16] {
+    let mut block = [0u8; 64];
+    block[..message.len()].copy_from_slice(message);
+    block[message.len()] = 0x80;
+    block[56..].copy_from_slice(&((message.len() as u64) * 8).to_be_bytes());
+
+    let mut words = [0u32; 16];
+    for (word, bytes) in words.iter_mut().zip(block.chunks_exact(4)) {
+        *word = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+    }
+    words
+}
+
+#[inline(always)]
+fn compress<V: Lanes>(mut w: [V; 16]) -> [V; 5] {
+    let (mut a, mut b, mut c, mut d, mut e) = (
+        V::splat(H[0]),
+        V::splat(H[1]),
+        V::splat(H[2]),
+        V::splat(H[3]),
+        V::splat(H[4]),
+    );
+
+    for i in 0..80 {
+        if i >= 16 {
+            w[i & 15] = w[(i - 3) & 15]
+                .xor(w[(i - 8) & 15])
+                .xor(w[(i - 14) & 15])
+                .xor(w[i & 15])
+                .rotl1();
+        }
+        let f = match i / 20 {
+            0 => b.and(c).or(b.andnot(d)),
+            2 => b.and(c).or(b.and(d)).or(c.and(d)),
+            _ => b.xor(c).xor(d),
+        };
+        let temp = a
+            .rotl5()
+            .add(f)
+            .add(e)
+            .add(V::splat(K[i / 20]))
+            .add(w[i & 15]);
+        e = d;
+        d = c;
+        c = b.rotl30();
+        b = a;
+        a = temp;
+    }
+
+    [
+        a.add(V::splat(H[0])),
+        b.add(V::splat(H[1])),
+        c.add(V::splat(H[2])),
+        d.add(V::splat(H[3])),
+        e.add(V::splat(H[4])),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{digest_batch, Backend};
+    use sha1::{Digest, Sha1};
+
+    fn check(backend: Backend) {
+        // Every length around the single block limit, and a partial last chunk
+        let candidates: Vec<Vec<u8>> = (0..71).map(|len| (0..len).map(|i| (i * 7 + len) as u8).collect()).collect();
+        let candidates: Vec<&[u8]> = candidates.iter().map(|candidate| candidate.as_slice()).collect();
+
+        let digests = digest_batch(backend, &candidates);
+        for (candidate, digest) in candidates.iter().zip(digests.iter()) {
+            assert_eq!(digest.as_slice(), Sha1::digest(candidate).as_slice());
+        }
+    }
+
+    #[test]
+    fn scalar() {
+        check(Backend::Scalar);
+    }
+
+    // Backends the CPU lacks fall back to the scalar path, so these run everywhere
+    #[test]
+    fn sse2() {
+        check(Backend::Sse2);
+    }
+
+    #[test]
+    fn avx2() {
+        check(Backend::Avx2);
+    }
+}
@@ -0,0 +1,97 @@
+//! Potfile: the passwords recovered by earlier runs, one `hash:password` line each.
+//!
+//! The hash is written as it was given, so it may itself contain colons (pwdump, NetNTLM...).
+//! When reading, the password starts after the longest prefix that parses as a hash.
+
+use crate::{hashes, Error};
+use std::{
+    collections::HashSet,
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::Path,
+};
+
+// The passwords of a potfile, a missing potfile is empty
+pub fn read(path: &Path) -> Result<Vec<String>, Error> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(err.into()),
+    };
+
+    Ok(content
+        .lines()
+        .filter_map(split)
+        .map(|(_, password)| password.to_string())
+        .collect())
+}
+
+// Add the cracks that the potfile does not hold yet
+pub fn append<'a>(path: &Path, entries: impl IntoIterator<Item = (&'a str, &'a str)>) -> Result<(), Error> {
+    let existing = fs::read_to_string(path).unwrap_or_default();
+    let mut known: HashSet<String> = existing.lines().map(str::to_string).collect();
+
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for (hash, password) in entries {
+        let line = line(hash, password);
+        if !known.contains(&line) {
+            writeln!(file, "{}", line)?;
+            known.insert(line);
+        }
+    }
+    Ok(())
+}
+
+pub fn line(hash: &str, password: &str) -> String {
+    format!("{}:{}", hash.trim(), password)
+}
+
+// The password of a line that starts with a hash we recognise
+pub fn password(line: &str) -> Option<&str> {
+    hash_end(line).map(|index| &line[index + 1..])
+}
+
+fn split(line: &str) -> Option<(&str, &str)> {
+    hash_end(line)
+        .or_else(|| line.rfind(':'))
+        .map(|index| (&line[..index], &line[index + 1..]))
+}
+
+fn hash_end(line: &str) -> Option<usize> {
+    line.rmatch_indices(':')
+        .map(|(index, _)| index)
+        .find(|index| hashes::parse(&line[..*index]).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_lines() {
+        assert_eq!(
+            split("5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8:pass:word"),
+            Some(("5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8", "pass:word"))
+        );
+        assert_eq!(
+            split("Administrator:500:aad3b435b51404eeaad3b435b51404ee:31d6cfe0d16ae931b73c59d7e0c089c0::::"),
+            Some(("Administrator:500:aad3b435b51404eeaad3b435b51404ee:31d6cfe0d16ae931b73c59d7e0c089c0:::", ""))
+        );
+        assert_eq!(split("unknown:pass:word"), Some(("unknown:pass", "word")));
+        assert_eq!(password("unknown:pass:word"), None);
+        assert_eq!(split("no colon"), None);
+    }
+
+    #[test]
+    fn append_and_read() {
+        let path = std::env::temp_dir().join(format!("sha1_cracker_potfile_{}", std::process::id()));
+        append(&path, [("bob:5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8", "password")]).unwrap();
+        append(&path, [("7c4a8d09ca3762af61e59520943dc26494f8941b", "123456")]).unwrap();
+        append(&path, [("bob:5baa61e4c9b93f3f0682250b6cf8331b7ee68fd8", "password")]).unwrap();
+        let passwords = read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(passwords, vec!["password", "123456"]);
+        assert!(read(&path).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,151 @@
+//! Single crack mode: guesses each user's password from their own account details.
+//!
+//! Lines are `user:hash:gecos`, full passwd entries (`user:hash:uid:gid:gecos:home:shell`) or
+//! pwdump lines. The login name, the full name from the GECOS field and the home directory are
+//! mangled into candidates that are only tested against the hash of that same user, which keeps
+//! the mode fast enough to run before any wordlist.
+
+use crate::{
+    cracker::Crack,
+    hashes::{self, Hash, Ntlm},
+    mangle, Error,
+};
+
+/*
+A Target is a hash along with the words its owner is likely to have used in their password.
+    - `line`: The hash as `user:hash` (or the pwdump line), which is how it is reported.
+    - `hash`: The parsed hash.
+    - `words`: Base words taken from the account.
+*/
+
+pub struct Target {
+    pub line: String,
+    pub hash: Box<dyn Hash>,
+    pub words: Vec<String>,
+}
+
+/*
+Function definitions for single crack mode
+    - `parse`: Splits a passwd-like line into the hash and the words of its user.
+    - `crack`: Tests the mangled words of every target against its own hash.
+    - `words`: Builds the base words from a login, a GECOS field and a home directory.
+*/
+
+pub fn parse(line: &str) -> Result<Target, Error> {
+    let line = line.trim();
+
+    if Ntlm::matches_pwdump(line) {
+        let user = line.split(':').next().unwrap_or_default();
+        return Ok(Target {
+            line: line.to_string(),
+            hash: hashes::parse(line)?,
+            words: words(user, "", ""),
+        });
+    }
+
+    let fields: Vec<&str> = line.split(':').collect();
+    let (user, hash, gecos, home) = match fields.as_slice() {
+        [user, hash, _uid, _gid, gecos, home, _shell] => (*user, *hash, gecos.to_string(), *home),
+        [user, hash, gecos @ ..] => (*user, *hash, gecos.join(":"), ""),
+        _ => return Err(Error::InvalidHash(line.to_string())),
+    };
+
+    let line = format!("{}:{}", user, hash);
+    Ok(Target {
+        hash: hashes::parse(&line)?,
+        line,
+        words: words(user, &gecos, home),
+    })
+}
+
+pub fn crack(targets: &[Target]) -> Vec<Crack> {
+    targets
+        .iter()
+        .enumerate()
+        .filter_map(|(index, target)| {
+            mangle::variants(&target.words)
+                .into_iter()
+                .find(|candidate| target.hash.verify(candidate.as_bytes()))
+                .map(|password| Crack { index, password })
+        })
+        .collect()
+}
+
+fn words(user: &str, gecos: &str, home: &str) -> Vec<String> {
+    let mut words = vec![user.to_string()];
+
+    // Logins like jsmith01 often hide the name without the digits
+    let trimmed = user.trim_end_matches(|c: char| c.is_ascii_digit());
+    words.push(trimmed.to_string());
+
+    // The full name is the first comma separated GECOS field
+    let name = gecos.split(',').next().unwrap_or_default();
+    let parts: Vec<&str> = name.split(|c: char| !c.is_alphanumeric()).filter(|part| !part.is_empty()).collect();
+    words.extend(parts.iter().map(|part| part.to_string()));
+
+    if let (Some(first), Some(last)) = (parts.first(), parts.last()) {
+        if parts.len() > 1 {
+            let initial = |word: &str| word.chars().next().map(String::from).unwrap_or_default();
+            words.push(parts.concat());
+            words.push(format!("{}{}", last, first));
+            words.push(format!("{}{}", initial(first), last));
+            words.push(format!("{}{}", first, initial(last)));
+            words.push(parts.iter().map(|part| initial(part)).collect());
+        }
+    }
+
+    if let Some(dir) = home.trim_end_matches('/').rsplit('/').next() {
+        words.push(dir.to_string());
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    words.retain(|word| !word.is_empty() && seen.insert(word.to_lowercase()));
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha1::{Digest, Sha1};
+
+    fn sha1(password: &str) -> String {
+        hex::encode(Sha1::digest(password.as_bytes()))
+    }
+
+    #[test]
+    fn words_from_gecos() {
+        let words = words("jsmith01", "John Smith,Room 101,555-1234,", "/home/jsmith01");
+        assert_eq!(words, vec!["jsmith01", "jsmith", "John", "Smith", "JohnSmith", "SmithJohn", "JohnS", "JS"]);
+    }
+
+    #[test]
+    fn parse_formats() {
+        let hash = sha1("x");
+        let target = parse(&format!("bob:{}:Bob Marley", hash)).unwrap();
+        assert_eq!(target.words, vec!["bob", "Marley", "BobMarley", "MarleyBob", "BMarley", "BobM", "BM"]);
+        assert_eq!(target.hash.label().unwrap(), "bob");
+
+        let target = parse(&format!("alice:{}:1000:1000:Alice Liddell,,,:/home/wonderland:/bin/bash", hash)).unwrap();
+        assert!(target.words.contains(&"wonderland".to_string()));
+        assert!(target.words.contains(&"Liddell".to_string()));
+
+        let target = parse("Administrator:500:aad3b435b51404eeaad3b435b51404ee:31d6cfe0d16ae931b73c59d7e0c089c0:::").unwrap();
+        assert_eq!(target.words, vec!["Administrator"]);
+
+        assert!(parse("no-hash-here").is_err());
+    }
+
+    #[test]
+    fn crack_own_hash_only() {
+        let targets = vec![
+            parse(&format!("jsmith:{}:John Smith", sha1("Smith2024"))).unwrap(),
+            parse(&format!("bob:{}:Bob Marley", sha1("htimS"))).unwrap(),
+            parse(&format!("carol:{}:Carol King", sha1("C@r0l!"))).unwrap(),
+        ];
+
+        let cracks = crack(&targets);
+        assert_eq!(cracks.len(), 2);
+        assert_eq!((cracks[0].index, cracks[0].password.as_str()), (0, "Smith2024"));
+        assert_eq!((cracks[1].index, cracks[1].password.as_str()), (2, "C@r0l!"));
+    }
+}
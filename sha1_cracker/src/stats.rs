@@ -0,0 +1,241 @@
+//! Password analytics: what a set of cracked passwords looks like.
+//!
+//! Works on plaintexts, either straight from a potfile or from a list with one password per
+//! line. The report covers lengths, character classes, hashcat masks, base words, trailing
+//! years and dates, and how many passwords satisfy a length and complexity policy.
+
+use std::collections::HashMap;
+
+/*
+Policy is the password policy the passwords are checked against.
+    - `min_length`: Minimum number of characters.
+    - `min_classes`: Minimum number of character classes (lower, upper, digit, special).
+*/
+
+pub struct Policy {
+    pub min_length: usize,
+    pub min_classes: usize,
+}
+
+/*
+Stats holds the analysis of a list of passwords, every list is sorted from most to least common.
+    - `total`, `unique`: Number of passwords and of distinct passwords.
+    - `lengths`: Number of passwords per length, sorted by length.
+    - `charsets`: Character class combinations (`loweralphanum`, `mixedalphaspecialnum`...).
+    - `masks`: hashcat masks (`?u?l?l?l?d?d`).
+    - `base_words`: Lowercased passwords without leading and trailing digits and symbols.
+    - `years`: Years between 1950 and 2049 at the end of passwords.
+    - `dates`: Number of passwords ending with a date (ddmmyyyy, mmddyyyy, yyyymmdd, ddmmyy...).
+    - `compliant`: Number of passwords that satisfy the policy.
+*/
+
+pub struct Stats {
+    pub total: usize,
+    pub unique: usize,
+    pub lengths: Vec<(usize, usize)>,
+    pub charsets: Vec<(String, usize)>,
+    pub masks: Vec<(String, usize)>,
+    pub base_words: Vec<(String, usize)>,
+    pub years: Vec<(String, usize)>,
+    pub dates: usize,
+    pub compliant: usize,
+}
+
+#[derive(Default)]
+struct Classes {
+    lower: bool,
+    upper: bool,
+    digit: bool,
+    special: bool,
+}
+
+impl Classes {
+    fn of(password: &str) -> Self {
+        let mut classes = Classes::default();
+        for c in password.chars() {
+            match c {
+                'a'..='z' => classes.lower = true,
+                'A'..='Z' => classes.upper = true,
+                '0'..='9' => classes.digit = true,
+                _ => classes.special = true,
+            }
+        }
+        classes
+    }
+
+    fn count(&self) -> usize {
+        [self.lower, self.upper, self.digit, self.special].iter().filter(|class| **class).count()
+    }
+
+    // Same names as the PACK password analysis toolkit
+    fn name(&self) -> String {
+        let alpha = match (self.lower, self.upper) {
+            (true, true) => "mixedalpha",
+            (true, false) => "loweralpha",
+            (false, true) => "upperalpha",
+            (false, false) => "",
+        };
+        match (alpha, self.special, self.digit) {
+            ("", false, true) => "numeric".to_string(),
+            ("", false, false) => "empty".to_string(),
+            (alpha, special, digit) => {
+                format!("{}{}{}", alpha, if special { "special" } else { "" }, if digit { "num" } else { "" })
+            }
+        }
+    }
+}
+
+pub fn analyse(passwords: &[String], policy: &Policy) -> Stats {
+    let mut unique = HashMap::new();
+    let mut lengths = HashMap::new();
+    let mut charsets = HashMap::new();
+    let mut masks = HashMap::new();
+    let mut base_words = HashMap::new();
+    let mut years = HashMap::new();
+    let mut dates = 0;
+    let mut compliant = 0;
+
+    for password in passwords {
+        *unique.entry(password.as_str()).or_insert(0) += 1;
+        let length = password.chars().count();
+        *lengths.entry(length).or_insert(0) += 1;
+
+        let classes = Classes::of(password);
+        *charsets.entry(classes.name()).or_insert(0) += 1;
+        *masks.entry(mask(password)).or_insert(0) += 1;
+        if length >= policy.min_length && classes.count() >= policy.min_classes {
+            compliant += 1;
+        }
+
+        if let Some(word) = base_word(password) {
+            *base_words.entry(word).or_insert(0) += 1;
+        }
+        if let Some(year) = year_suffix(password) {
+            *years.entry(year.to_string()).or_insert(0) += 1;
+        }
+        if has_date_suffix(password) {
+            dates += 1;
+        }
+    }
+
+    let mut lengths: Vec<(usize, usize)> = lengths.into_iter().collect();
+    lengths.sort();
+
+    Stats {
+        total: passwords.len(),
+        unique: unique.len(),
+        lengths,
+        charsets: ranked(charsets),
+        masks: ranked(masks),
+        base_words: ranked(base_words),
+        years: ranked(years),
+        dates,
+        compliant,
+    }
+}
+
+// Most common first, ties in alphabetical order so the report is stable
+fn ranked(counts: HashMap<String, usize>) -> Vec<(String, usize)> {
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+fn mask(password: &str) -> String {
+    password
+        .chars()
+        .map(|c| match c {
+            'a'..='z' => "?l",
+            'A'..='Z' => "?u",
+            '0'..='9' => "?d",
+            c if c.is_ascii() => "?s",
+            _ => "?b",
+        })
+        .collect()
+}
+
+fn base_word(password: &str) -> Option<String> {
+    let word = password.trim_matches(|c: char| !c.is_alphabetic());
+    (word.chars().count() >= 3).then(|| word.to_lowercase())
+}
+
+fn trailing_digits(password: &str) -> &str {
+    let start = password.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    &password[start..]
+}
+
+fn year_suffix(password: &str) -> Option<&str> {
+    let digits = trailing_digits(password.trim_end_matches(|c: char| !c.is_alphanumeric()));
+    let year = digits.get(digits.len().checked_sub(4)?..)?;
+    (1950..2050).contains(&year.parse::<u32>().ok()?).then_some(year)
+}
+
+fn has_date_suffix(password: &str) -> bool {
+    let digits = trailing_digits(password.trim_end_matches(|c: char| !c.is_alphanumeric()));
+    let parts = |layout: &[(usize, usize)]| -> Option<Vec<u32>> {
+        layout.iter().map(|(start, end)| digits.get(*start..*end)?.parse().ok()).collect()
+    };
+    let valid = |day: u32, month: u32| (1..=31).contains(&day) && (1..=12).contains(&month);
+    let year = |year: u32| (1950..2050).contains(&year);
+
+    match digits.len() {
+        8 => {
+            let dmy = parts(&[(0, 2), (2, 4), (4, 8)]).unwrap_or_default();
+            let ymd = parts(&[(0, 4), (4, 6), (6, 8)]).unwrap_or_default();
+            (year(dmy[2]) && (valid(dmy[0], dmy[1]) || valid(dmy[1], dmy[0])))
+                || (year(ymd[0]) && valid(ymd[2], ymd[1]))
+        }
+        6 => {
+            let dmy = parts(&[(0, 2), (2, 4), (4, 6)]).unwrap_or_default();
+            valid(dmy[0], dmy[1]) || valid(dmy[1], dmy[0])
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passwords(list: &[&str]) -> Vec<String> {
+        list.iter().map(|password| password.to_string()).collect()
+    }
+
+    #[test]
+    fn report() {
+        let list = passwords(&["Summer2024!", "summer2024", "password", "password", "Jan15031987", "123456", "P@ss wörd"]);
+        let stats = analyse(&list, &Policy { min_length: 8, min_classes: 3 });
+
+        assert_eq!((stats.total, stats.unique), (7, 6));
+        assert_eq!(stats.lengths, vec![(6, 1), (8, 2), (9, 1), (10, 1), (11, 2)]);
+        assert_eq!(stats.charsets[0], ("loweralpha".to_string(), 2));
+        assert!(stats.charsets.contains(&("mixedalphaspecialnum".to_string(), 1)));
+        assert!(stats.charsets.contains(&("numeric".to_string(), 1)));
+        assert_eq!(stats.masks[0], ("?l?l?l?l?l?l?l?l".to_string(), 2));
+        assert!(stats.masks.contains(&("?u?s?l?l?s?l?b?l?l".to_string(), 1)));
+        assert_eq!(stats.base_words[0], ("password".to_string(), 2));
+        assert_eq!(stats.base_words[1], ("summer".to_string(), 2));
+        assert_eq!(stats.years, vec![("2024".to_string(), 2), ("1987".to_string(), 1)]);
+        assert_eq!(stats.dates, 1);
+        // Summer2024!, Jan15031987 and P@ss wörd
+        assert_eq!(stats.compliant, 3);
+    }
+
+    #[test]
+    fn charset_names() {
+        assert_eq!(Classes::of("abc").name(), "loweralpha");
+        assert_eq!(Classes::of("ABC1").name(), "upperalphanum");
+        assert_eq!(Classes::of("!!").name(), "special");
+        assert_eq!(Classes::of("!1").name(), "specialnum");
+        assert_eq!(Classes::of("aB!").name(), "mixedalphaspecial");
+    }
+
+    #[test]
+    fn dates() {
+        assert!(has_date_suffix("bob25121990"));
+        assert!(has_date_suffix("bob19901225!"));
+        assert!(has_date_suffix("bob122590"));
+        assert!(!has_date_suffix("bob99999999"));
+        assert!(!has_date_suffix("bob2024"));
+    }
+}
@@ -0,0 +1,289 @@
+//! Wordlist generation from a client's own content (a website mirror, documents...).
+//!
+//! HTML is reduced to its visible text plus the attributes people read (alt, title, meta
+//! content...), scripts and styles are dropped. From the text we keep words, runs of
+//! capitalised words glued together (names and products such as AcmeCloud) and the local part
+//! of email addresses, each counted so rare words can be filtered out.
+
+use crate::Error;
+use std::{collections::HashMap, fs, path::Path};
+
+const ATTRIBUTES: &[&str] = &["alt", "title", "content", "placeholder", "value", "label"];
+const MAX_PHRASE: usize = 3;
+
+/*
+Filters limits which words make it to the wordlist.
+    - `min_length`, `max_length`: Bounds on the number of characters of a word.
+    - `min_count`: Minimum number of occurrences across all the files.
+*/
+
+pub struct Filters {
+    pub min_length: usize,
+    pub max_length: usize,
+    pub min_count: usize,
+}
+
+#[derive(Default)]
+pub struct Generator {
+    counts: HashMap<String, usize>,
+}
+
+impl Generator {
+    // Add a file, or every file under a directory
+    pub fn add_path(&mut self, path: &Path) -> Result<(), Error> {
+        if path.is_dir() {
+            let mut entries = fs::read_dir(path)?.collect::<Result<Vec<_>, _>>()?;
+            entries.sort_by_key(|entry| entry.path());
+            for entry in entries {
+                self.add_path(&entry.path())?;
+            }
+            return Ok(());
+        }
+
+        let content = fs::read(path)?;
+        // Images, archives and other binary files of a mirror
+        if content.iter().take(8192).any(|byte| *byte == 0) {
+            return Ok(());
+        }
+        let content = String::from_utf8_lossy(&content);
+        if is_html(path, &content) {
+            self.add_html(&content);
+        } else {
+            self.add_text(&content);
+        }
+        Ok(())
+    }
+
+    pub fn add_html(&mut self, html: &str) {
+        // Emails are often only in mailto: links
+        self.add_emails(html);
+        self.add_words(&decode_entities(&strip_html(html)));
+    }
+
+    pub fn add_text(&mut self, text: &str) {
+        self.add_emails(text);
+        self.add_words(text);
+    }
+
+    // Most common words first
+    pub fn words(&self, filters: &Filters) -> Vec<(String, usize)> {
+        let mut words: Vec<(String, usize)> = self
+            .counts
+            .iter()
+            .filter(|(word, count)| {
+                let length = word.chars().count();
+                length >= filters.min_length && length <= filters.max_length && **count >= filters.min_count
+            })
+            .map(|(word, count)| (word.clone(), *count))
+            .collect();
+        words.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        words
+    }
+
+    fn add(&mut self, word: &str) {
+        if word.chars().any(char::is_alphabetic) {
+            *self.counts.entry(word.to_string()).or_insert(0) += 1;
+        }
+    }
+
+    fn add_words(&mut self, text: &str) {
+        for line in text.lines() {
+            self.add_line(line);
+        }
+    }
+
+    fn add_line(&mut self, line: &str) {
+        // Capitalised words only form a phrase when nothing but spaces separates them
+        let mut phrase: Vec<&str> = vec![];
+        for chunk in line.split_whitespace() {
+            let words: Vec<&str> = chunk.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()).collect();
+            for word in &words {
+                self.add(word);
+            }
+
+            let capitalised = words.len() == 1
+                && chunk.starts_with(words[0])
+                && words[0].chars().next().is_some_and(char::is_uppercase);
+            if capitalised {
+                phrase.push(words[0]);
+                for start in phrase.len().saturating_sub(MAX_PHRASE)..phrase.len() - 1 {
+                    self.add(&phrase[start..].concat());
+                }
+            }
+            // A trailing comma or full stop ends the phrase after this word
+            if !capitalised || !chunk.ends_with(words[0]) {
+                phrase.clear();
+            }
+        }
+    }
+
+    fn add_emails(&mut self, text: &str) {
+        let is_local = |c: char| c.is_alphanumeric() || "._%+-".contains(c);
+        for (at, _) in text.match_indices('@') {
+            let local = &text[..at];
+            let start = local.trim_end_matches(is_local).len();
+            let local = local[start..].trim_matches(|c: char| !c.is_alphanumeric());
+            let domain_starts = text[at + 1..].chars().next().is_some_and(char::is_alphanumeric);
+            if !local.is_empty() && domain_starts {
+                self.add(local);
+                if local.contains(|c: char| !c.is_alphanumeric()) {
+                    for part in local.split(|c: char| !c.is_alphanumeric()).filter(|part| !part.is_empty()) {
+                        self.add(part);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn is_html(path: &Path, content: &str) -> bool {
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default();
+    matches!(extension.to_ascii_lowercase().as_str(), "html" | "htm" | "xhtml" | "php" | "asp" | "aspx" | "jsp")
+        || content.trim_start().get(..5).is_some_and(|start| start.eq_ignore_ascii_case("<!doc") || start.eq_ignore_ascii_case("<html"))
+}
+
+// Visible text and readable attributes of a page, each tag starts a new line
+fn strip_html(html: &str) -> String {
+    let lower = html.to_ascii_lowercase();
+    let mut text = String::new();
+    let mut position = 0;
+
+    while let Some(offset) = html[position..].find('<') {
+        text.push_str(&html[position..position + offset]);
+        text.push('\n');
+        let start = position + offset;
+
+        let skip_to = |end: &str| lower[start..].find(end).map_or(html.len(), |index| start + index + end.len());
+        position = if lower[start..].starts_with("<!--") {
+            skip_to("-->")
+        } else if lower[start..].starts_with("<script") {
+            skip_to("</script>")
+        } else if lower[start..].starts_with("<style") {
+            skip_to("</style>")
+        } else {
+            let end = skip_to(">");
+            for value in attribute_values(&html[start..end]) {
+                text.push_str(value);
+                text.push('\n');
+            }
+            end
+        };
+    }
+    text.push_str(&html[position..]);
+    text
+}
+
+fn attribute_values(tag: &str) -> Vec<&str> {
+    let lower = tag.to_ascii_lowercase();
+    let mut values = vec![];
+    for attribute in ATTRIBUTES {
+        for (index, _) in lower.match_indices(&format!("{}=", attribute)) {
+            // Only whole attribute names, not the end of data-title=
+            if index > 0 && !lower.as_bytes()[index - 1].is_ascii_whitespace() {
+                continue;
+            }
+            let rest = &tag[index + attribute.len() + 1..];
+            let value = match rest.chars().next() {
+                Some(quote @ ('"' | '\'')) => rest[1..].split(quote).next(),
+                _ => rest.split(|c: char| c.is_whitespace() || c == '>').next(),
+            };
+            values.extend(value);
+        }
+    }
+    values
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let entity = rest.find(';').filter(|end| *end <= 10).map(|end| &rest[1..end]);
+        let character = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => {
+                let number = entity.strip_prefix('#')?;
+                let code = match number.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => number.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+
+        match (entity, character) {
+            (Some(entity), Some(character)) => {
+                decoded.push(character);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAGE: &str = r#"<!DOCTYPE html>
+<html><head><title>Acme Cloud Suite</title>
+<meta name="description" content="Rocketship analytics">
+<style>.banner { color: red }</style>
+<script>var secretVariable = 1;</script></head>
+<body><!-- hidden comment -->
+<img src="logo.png" alt="Acme logo" data-title="ignored">
+<p>Welcome to Acme Cloud Suite, built in Springfield &amp; Shelbyville.</p>
+<a href="mailto:john.smith@acme.example">Contact us</a> caf&eacute; na&#xEF;ve
+</body></html>"#;
+
+    fn words(generator: &Generator, min_count: usize) -> Vec<String> {
+        let filters = Filters {
+            min_length: 3,
+            max_length: 20,
+            min_count,
+        };
+        generator.words(&filters).into_iter().map(|(word, _)| word).collect()
+    }
+
+    #[test]
+    fn html_page() {
+        let mut generator = Generator::default();
+        generator.add_html(PAGE);
+        let words = words(&generator, 1);
+
+        for expected in ["Acme", "AcmeCloud", "AcmeCloudSuite", "CloudSuite", "Rocketship", "Springfield", "Shelbyville", "logo", "john.smith", "john", "smith", "Welcome", "naïve"] {
+            assert!(words.contains(&expected.to_string()), "{}", expected);
+        }
+        for unexpected in ["secretVariable", "banner", "hidden", "ignored", "html", "SuiteBuilt", "SuiteRocketship", "SpringfieldShelbyville"] {
+            assert!(!words.contains(&unexpected.to_string()), "{}", unexpected);
+        }
+        // Unknown entities are left alone
+        assert!(words.contains(&"caf".to_string()));
+        assert_eq!(words[0], "Acme");
+    }
+
+    #[test]
+    fn filters() {
+        let mut generator = Generator::default();
+        generator.add_text("alpha beta beta gamma gamma gamma to 2024 averyveryverylongwordindeed");
+        assert_eq!(words(&generator, 2), vec!["gamma", "beta"]);
+        assert_eq!(words(&generator, 1), vec!["gamma", "beta", "alpha"]);
+    }
+
+    #[test]
+    fn entities() {
+        assert_eq!(decode_entities("a &amp; b &#65;&#x42; &unknown; & c"), "a & b AB &unknown; & c");
+    }
+}
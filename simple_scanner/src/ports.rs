@@ -20,10 +20,10 @@ pub async fn scan_ports(mut subdomain: Subdomain, port_size: u16, concurrency: u
 		return subdomain;
 	}
 
-	subdomain.open_ports = stream::iter(MOST_COMMON_PORTS.into_iter().cloned())
+	subdomain.open_ports = stream::iter(MOST_COMMON_PORTS.iter().copied())
 		.take(port_size as usize)
 		.map(|port| {
-			let socket_address = socket_addresses[0].clone();
+			let socket_address = socket_addresses[0];
 			async move { scan_port(socket_address, port).await }
 		})
 		.buffer_unordered(concurrency)
@@ -20,7 +20,7 @@ pub async fn enumerate(http_client: &Client, target: &str) -> Result<Vec<Subdoma
 	
 	// Get subdomains from crt.sh
 	let entries: Vec<CrtShEntry> = http_client
-		.get(&format!("https://crt.sh/?q=%25.{}&output=json", target))
+		.get(format!("https://crt.sh/?q=%25.{}&output=json", target))
 		.send()
 		.await?
 		.json()
@@ -54,7 +54,7 @@ pub async fn enumerate(http_client: &Client, target: &str) -> Result<Vec<Subdoma
 	.expect("subdomain resolver: building  DNS client");
 
 	// Check if subdomains resolve to an IP address
-	let subdomains: Vec<Subdomain> = stream::iter(subdomains.into_iter())
+	let subdomains: Vec<Subdomain> = stream::iter(subdomains)
 		.map(|domain| Subdomain {
 			domain,
 			open_ports: Vec::new(),